@@ -0,0 +1,35 @@
+use aidoku::{
+    error::{AidokuError, AidokuErrorKind, NodeError},
+    prelude::*,
+    std::defaults::defaults_get,
+};
+use madokami_parser::SourceError;
+
+/// Converts a site error into the closest Aidoku error kind, logging the full
+/// message when diagnostics are on, since the kind alone can't say what went
+/// wrong. `SourceError` lives in the parser crate, so this can't be a `From`
+/// impl.
+pub fn aidoku_error(error: SourceError) -> AidokuError {
+    if diagnostics_enabled() {
+        println!("Madokami: {}", error.message());
+    }
+    let reason = match error {
+        SourceError::Unauthorized
+        | SourceError::NotFound(_)
+        | SourceError::Server(_)
+        | SourceError::Http(_) => AidokuErrorKind::DefaultNotFound,
+        SourceError::Layout(_) => AidokuErrorKind::NodeError(NodeError::QueryError),
+        SourceError::Archive(_) => AidokuErrorKind::DeserializeError,
+        SourceError::UnsupportedFormat(_) | SourceError::ArchiveTooLarge(_) => {
+            AidokuErrorKind::Unimplemented
+        }
+    };
+    AidokuError { reason }
+}
+
+/// Whether errors should be written to the Aidoku logs, set from the settings page.
+pub fn diagnostics_enabled() -> bool {
+    defaults_get("errorDiagnostics")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
@@ -0,0 +1,33 @@
+use aidoku::{
+    prelude::*,
+    std::{defaults::defaults_get, String},
+    Manga, MangaStatus, MangaViewer,
+};
+
+/// The ID of the placeholder entry shown when Madokami asks for credentials.
+pub const LOGIN_GUIDE_ID: &str = "aidoku/login";
+
+/// A placeholder entry explaining how to set up credentials, shown in place of
+/// an empty list when Madokami answers with its login wall.
+pub fn login_guide() -> Manga {
+    let status = defaults_get("loginStatus")
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    let mut description = String::from(
+        "Madokami requires an account. Enter your username and password in this source's \
+         settings, then pull to refresh.",
+    );
+    if !status.is_empty() {
+        description.push_str(&format!("\n\nLogin status: {}", status));
+    }
+    Manga {
+        id: String::from(LOGIN_GUIDE_ID),
+        cover: String::from("https://fakeimg.pl/550x780/ffffff/6e7b91/?font=museo&text=Login"),
+        title: String::from("Log in to Madokami"),
+        description,
+        status: MangaStatus::Unknown,
+        viewer: MangaViewer::Rtl,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,239 @@
+#![no_std]
+extern crate alloc;
+
+use aidoku::{
+    error::Result,
+    prelude::*,
+    std::{
+        current_date,
+        defaults::{defaults_get, defaults_set},
+        net::Request,
+        String, StringRef, Vec,
+    },
+    Chapter, DeepLink, Filter, FilterType, Manga, MangaPageResult, MangaStatus, MangaViewer, Page,
+};
+use alloc::vec;
+use base64::{engine::general_purpose, Engine};
+use madokami_parser::{
+    configured_base_url, Backend, ChapterInfo, Madokami, MangaInfo, PageInfo, PageSource, Settings,
+    SourceError, SourceResult, IMAGE_ACCEPT,
+};
+
+mod error;
+mod helper;
+mod net;
+use error::*;
+use helper::*;
+use net::*;
+
+/// The Madokami URL to use, from the settings so a mirror or caching proxy can
+/// be used when the domain moves.
+fn base_url() -> String {
+    let url = defaults_get("baseUrl")
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    configured_base_url(&url)
+}
+
+/// Whether filename parsing should be logged, set from the settings page.
+fn parser_debug_enabled() -> bool {
+    defaults_get("parserDebug")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Whether scanlator credit and recruitment pages are left out of chapters.
+fn skip_credit_pages() -> bool {
+    defaults_get("skipCreditPages")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Whether only the smallest file is listed when several share a chapter.
+fn smallest_duplicates() -> bool {
+    defaults_get("smallestDuplicates")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// The backend chosen on the settings page.
+fn backend() -> Backend {
+    let backend = defaults_get("backend")
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    Backend::from_setting(&backend)
+}
+
+/// Runs `handler` against the live site with the current settings, writing
+/// any parser diagnostics to the Aidoku log.
+fn with_site<T, F>(handler: F) -> SourceResult<T>
+where
+    F: FnOnce(&Madokami<LiveNetwork>) -> SourceResult<T>,
+{
+    let settings = Settings {
+        base_url: base_url(),
+        backend: backend(),
+        skip_credit_pages: skip_credit_pages(),
+        smallest_duplicates: smallest_duplicates(),
+        parser_debug: parser_debug_enabled(),
+        now: current_date(),
+    };
+    let site = Madokami::new(LiveNetwork, settings);
+    let result = handler(&site);
+    for line in site.take_log() {
+        println!("{}", line);
+    }
+    result
+}
+
+fn manga(info: MangaInfo) -> Manga {
+    Manga {
+        id: info.id,
+        title: info.title,
+        author: info.author,
+        cover: info.cover,
+        description: info.description,
+        categories: info.categories,
+        url: info.url,
+        status: if info.completed {
+            MangaStatus::Completed
+        } else {
+            MangaStatus::Unknown
+        },
+        viewer: MangaViewer::Rtl,
+        ..Default::default()
+    }
+}
+
+fn chapter(info: ChapterInfo) -> Chapter {
+    Chapter {
+        id: info.id,
+        title: info.title,
+        chapter: info.chapter,
+        volume: info.volume,
+        date_updated: info.date_updated,
+        scanlator: info.scanlator,
+        url: info.url,
+        ..Default::default()
+    }
+}
+
+fn page(info: PageInfo) -> Page {
+    match info.source {
+        PageSource::Url(url) => Page {
+            index: info.index,
+            url,
+            ..Default::default()
+        },
+        PageSource::Image(image) => Page {
+            index: info.index,
+            base64: general_purpose::STANDARD.encode(image),
+            ..Default::default()
+        },
+        PageSource::Text(text) => Page {
+            index: info.index,
+            text,
+            ..Default::default()
+        },
+    }
+}
+
+#[handle_url]
+fn handle_url(url: String) -> Result<DeepLink> {
+    let target = with_site(|site| site.handle_url(&url)).map_err(aidoku_error)?;
+    Ok(DeepLink {
+        manga: Some(manga(target.manga)),
+        chapter: target.chapter_id.map(|id| Chapter {
+            id,
+            ..Default::default()
+        }),
+    })
+}
+
+#[initialize]
+fn initialize() {
+    source_common::live::set_rate_limit();
+}
+
+#[get_manga_list]
+fn get_manga_list(filters: Vec<Filter>, page: i32) -> Result<MangaPageResult> {
+    let query = filters
+        .into_iter()
+        .find(|f| matches!(f.kind, FilterType::Title))
+        .and_then(|f| f.value.as_string().ok())
+        .map(|s| s.read());
+
+    match with_site(|site| site.manga_list(query.as_deref(), page)) {
+        Ok((mangas, has_more)) => Ok(MangaPageResult {
+            manga: mangas.into_iter().map(manga).collect(),
+            has_more,
+        }),
+        Err(SourceError::Unauthorized) => Ok(MangaPageResult {
+            manga: vec![login_guide()],
+            has_more: false,
+        }),
+        Err(error) => Err(aidoku_error(error)),
+    }
+}
+
+#[get_chapter_list]
+fn get_chapter_list(id: String) -> Result<Vec<Chapter>> {
+    if id == LOGIN_GUIDE_ID {
+        return Ok(Vec::new());
+    }
+    let chapters = with_site(|site| site.chapter_list(&id)).map_err(aidoku_error)?;
+    Ok(chapters.into_iter().map(chapter).collect())
+}
+
+#[get_manga_details]
+fn get_manga_details(id: String) -> Result<Manga> {
+    if id == LOGIN_GUIDE_ID {
+        return Ok(login_guide());
+    }
+    let details = with_site(|site| site.manga_details(&id)).map_err(aidoku_error)?;
+    Ok(manga(details))
+}
+
+#[get_page_list]
+fn get_page_list(_manga_id: String, chapter_id: String) -> Result<Vec<Page>> {
+    let pages = with_site(|site| site.page_list(&chapter_id)).map_err(aidoku_error)?;
+    Ok(pages.into_iter().map(page).collect())
+}
+
+#[modify_image_request]
+fn modify_image_request(request: Request) -> Request {
+    with_headers(request, IMAGE_ACCEPT)
+}
+
+#[handle_notification]
+fn handle_notification(notification: String) {
+    match notification.as_str() {
+        "username" | "password" => {
+            source_common::cache::clear();
+            verify_login();
+        }
+        "checkLogin" => verify_login(),
+        _ => {}
+    }
+}
+
+/// Probes Madokami with the stored credentials and records the outcome under
+/// `loginStatus`, which the settings and the login guide entry show.
+fn verify_login() {
+    let (username, password) = credentials();
+    let status = if username.is_empty() || password.is_empty() {
+        String::from("Not logged in")
+    } else {
+        match with_site(|site| site.check_login()) {
+            Ok(()) => format!("Logged in as {}", username),
+            Err(SourceError::Unauthorized) => String::from("Invalid credentials"),
+            Err(error) => format!("Could not verify login: {}", error.message()),
+        }
+    };
+    if diagnostics_enabled() {
+        println!("Madokami: {}", status);
+    }
+    defaults_set("loginStatus", StringRef::from(&status).0);
+}
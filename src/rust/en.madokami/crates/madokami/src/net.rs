@@ -0,0 +1,150 @@
+use aidoku::std::{
+    defaults::{defaults_get, defaults_set},
+    html::Node,
+    net::Request,
+    String, StringRef, Vec,
+};
+use madokami_parser::{
+    same_host, ChapterIdStore, HtmlElement, Network, ParseHtml, Response, HTML_ACCEPT,
+};
+use source_common::{
+    cache,
+    live::{self, basic_auth},
+};
+
+use crate::base_url;
+
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/115.0.0.0 Safari/537.36";
+
+/// Reads the username and password from the settings.
+pub fn credentials() -> (String, String) {
+    let username = defaults_get("username")
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    let password = defaults_get("password")
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    (username, password)
+}
+
+/// Adds HTTP Basic authentication to the given request if credentials are
+/// provided and the request goes to the configured Madokami host. Covers are
+/// often hotlinked from other sites, which must never see the password.
+fn add_auth_to_request(request: Request) -> Request {
+    let (username, password) = credentials();
+    match basic_auth(&username, &password) {
+        Some(auth) if same_host(&request.url().read(), &base_url()) => {
+            request.header("Authorization", &auth)
+        }
+        _ => request,
+    }
+}
+
+/// The user agent sent with every request, overridable in the settings for
+/// when Madokami starts blocking the default one.
+fn user_agent() -> String {
+    let user_agent = defaults_get("userAgent")
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    if user_agent.trim().is_empty() {
+        String::from(DEFAULT_USER_AGENT)
+    } else {
+        String::from(user_agent.trim())
+    }
+}
+
+/// Applies the headers every Madokami request carries: credentials, user
+/// agent, `Accept` and `Referer`.
+pub fn with_headers(request: Request, accept: &str) -> Request {
+    add_auth_to_request(request)
+        .header("User-Agent", &user_agent())
+        .header("Accept", accept)
+        .header("Referer", &base_url())
+}
+
+/// Madokami over Aidoku's networking, with the stored credentials, retries
+/// and the page cache. Aidoku follows redirects on its own, so responses are
+/// never redirects.
+pub struct LiveNetwork;
+
+impl Network for LiveNetwork {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Response {
+        let (accept, extra): (Vec<_>, Vec<_>) = headers
+            .iter()
+            .partition(|(name, _)| name.eq_ignore_ascii_case("Accept"));
+        let accept = accept.first().map_or(HTML_ACCEPT, |(_, value)| value);
+        live::get(url, &extra, |request| with_headers(request, accept))
+    }
+
+    fn cached(&self, url: &str) -> Option<(Vec<u8>, String)> {
+        cache::get(url)
+    }
+
+    fn store(&self, url: &str, location: &str, body: &[u8]) {
+        cache::insert(url, location, body)
+    }
+}
+
+impl ChapterIdStore for LiveNetwork {
+    fn setting(&self, key: &str) -> Option<String> {
+        defaults_get(key)
+            .and_then(|v| v.as_string())
+            .map(|s| s.read())
+            .ok()
+    }
+
+    fn set_setting(&self, key: &str, value: &str) {
+        defaults_set(key, StringRef::from(value).0);
+    }
+}
+
+impl ParseHtml for LiveNetwork {
+    type Element = PageNode;
+
+    fn parse_html(&self, html: &str) -> Option<PageNode> {
+        Node::new(html.as_bytes()).ok().map(PageNode)
+    }
+}
+
+/// An element of a page parsed by Aidoku, for the parser's selectors.
+pub struct PageNode(Node);
+
+impl PageNode {
+    /// Splits a selection into its elements.
+    fn elements(selection: Node) -> Vec<Self> {
+        selection
+            .array()
+            .filter_map(|value| value.as_node().ok())
+            .map(PageNode)
+            .collect()
+    }
+}
+
+impl HtmlElement for PageNode {
+    fn select(&self, selector: &str) -> Vec<Self> {
+        Self::elements(self.0.select(selector))
+    }
+
+    fn name(&self) -> String {
+        self.0.tag_name().to_lowercase()
+    }
+
+    fn attr(&self, name: &str) -> String {
+        self.0.attr(name).read()
+    }
+
+    fn has_attr(&self, name: &str) -> bool {
+        self.0.has_attr(name)
+    }
+
+    fn text(&self) -> String {
+        self.0.text().read()
+    }
+
+    fn children(&self) -> Vec<Self> {
+        Self::elements(self.0.children())
+    }
+}
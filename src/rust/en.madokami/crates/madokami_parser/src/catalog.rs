@@ -0,0 +1,185 @@
+//! The OPDS backend: Madokami's catalog read as the same series and chapters
+//! as the website.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    canonical_path, extract_manga_title, parse_date, parse_feed, resolve_url, url_decode,
+    url_encode, ChapterIdStore, ListedFile, Madokami, MangaInfo, Network, OpdsEntry, OpdsFeed,
+    ParseHtml, SourceError, SourceResult, FEED_ACCEPT, REL_NEWEST,
+};
+
+/// Where Madokami's OPDS catalog lives. Folder feeds are at this path followed
+/// by the folder's path on the website, so manga IDs are the same for both
+/// backends.
+const OPDS_PATH: &str = "/opds";
+/// How many pages of a folder's feed are followed before giving up.
+const MAX_FEED_PAGES: usize = 20;
+
+impl<N: Network + ParseHtml + ChapterIdStore> Madokami<N> {
+    /// Fetches an OPDS feed and parses it, like `fetch_page`. Returns the feed
+    /// along with the URL it was found at, which its relative links resolve
+    /// against. Anything that isn't a feed, such as the login page, is reported
+    /// as `Unauthorized` when it asks for a password.
+    fn fetch_feed(&self, url: &str) -> SourceResult<(OpdsFeed, String)> {
+        let (data, location, cached) = match self.network.cached(url) {
+            Some((data, location)) => (data, location, true),
+            None => {
+                let (data, location) = self.fetch_body(url, FEED_ACCEPT)?;
+                (data, location, false)
+            }
+        };
+        let text = String::from_utf8_lossy(&data);
+        match parse_feed(&text) {
+            Some(feed) => {
+                if !cached {
+                    self.network.store(url, &location, &data);
+                }
+                Ok((feed, location))
+            }
+            None if text.contains("type=\"password\"") => Err(SourceError::Unauthorized),
+            None => Err(SourceError::Layout("the OPDS catalog didn't return a feed")),
+        }
+    }
+
+    /// The website path a catalog link points at, such as a series folder or a
+    /// chapter file.
+    fn site_path(&self, href: &str) -> String {
+        let path = self.strip_base_url(href);
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let path = match path.strip_prefix(OPDS_PATH) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            _ => path,
+        };
+        canonical_path(path)
+    }
+
+    /// The search results feed for `query`, from the search template the root
+    /// feed links to, or else Madokami's own search path.
+    fn search_url(&self, root: &str, query: &str) -> SourceResult<String> {
+        let (feed, location) = self.fetch_feed(root)?;
+        let url = match feed.link("search") {
+            Some(link) if link.href.contains("{searchTerms}") => {
+                resolve_url(&location, &link.href).replace("{searchTerms}", &url_encode(query))
+            }
+            _ => format!("{}/search?q={}", root, url_encode(query)),
+        };
+        Ok(url)
+    }
+
+    /// The feed of recently updated series, as linked from the root feed.
+    fn newest_url(&self, root: &str) -> SourceResult<String> {
+        let (feed, location) = self.fetch_feed(root)?;
+        let url = match feed.link(REL_NEWEST) {
+            Some(link) => resolve_url(&location, &link.href),
+            None => format!("{}/recent", root),
+        };
+        Ok(url)
+    }
+
+    /// Fetches page `page` of a paginated feed by following its `next` links.
+    /// Pages past the end are empty.
+    fn feed_page(&self, url: &str, page: i32) -> SourceResult<(OpdsFeed, String)> {
+        let (mut feed, mut location) = self.fetch_feed(url)?;
+        for _ in 1..page {
+            let next = match feed.link("next") {
+                Some(link) => resolve_url(&location, &link.href),
+                None => return Ok((OpdsFeed::default(), location)),
+            };
+            (feed, location) = self.fetch_feed(&next)?;
+        }
+        Ok((feed, location))
+    }
+
+    /// The series an entry is about: the folder it links to, or the folder a
+    /// recently uploaded file is in.
+    fn entry_manga(&self, location: &str, entry: &OpdsEntry) -> Option<MangaInfo> {
+        let id = if let Some(link) = entry.navigation() {
+            self.site_path(&resolve_url(location, &link.href))
+        } else {
+            let file = self.site_path(&resolve_url(location, &entry.acquisition()?.href));
+            canonical_path(file.rsplit_once('/').map_or("", |(folder, _)| folder))
+        };
+        if id == "/" {
+            return None;
+        }
+        Some(MangaInfo {
+            title: extract_manga_title(&id),
+            cover: entry
+                .thumbnail()
+                .map(|link| resolve_url(location, &link.href))
+                .unwrap_or_default(),
+            author: entry.authors.join(", "),
+            description: entry.summary.clone(),
+            categories: entry.categories.clone(),
+            url: format!("{}{}", self.base_url(), id),
+            id,
+            ..Default::default()
+        })
+    }
+
+    /// Lists the series in the search results for `query`, or the recently
+    /// updated ones without a query, from the OPDS catalog. Also says whether
+    /// there is another page.
+    pub(crate) fn opds_manga_list(
+        &self,
+        query: Option<&str>,
+        page: i32,
+    ) -> SourceResult<(Vec<MangaInfo>, bool)> {
+        let root = format!("{}{}", self.base_url(), OPDS_PATH);
+        let url = match query {
+            Some(query) => self.search_url(&root, query)?,
+            None => self.newest_url(&root)?,
+        };
+        let (feed, location) = self.feed_page(&url, page)?;
+
+        let mut mangas: Vec<MangaInfo> = Vec::new();
+        for entry in &feed.entries {
+            if let Some(manga) = self.entry_manga(&location, entry) {
+                if !mangas.iter().any(|listed| listed.id == manga.id) {
+                    mangas.push(manga);
+                }
+            }
+        }
+        Ok((mangas, feed.link("next").is_some()))
+    }
+
+    /// Lists the files in a Madokami folder from its OPDS feed, following the
+    /// feed's pages.
+    pub(crate) fn opds_folder_files(&self, id: &str) -> SourceResult<Vec<ListedFile>> {
+        let manga_title = extract_manga_title(id);
+        let listed_at = self.settings.now;
+        let mut url = format!("{}{}{}", self.base_url(), OPDS_PATH, id);
+        let mut files = Vec::new();
+
+        for _ in 0..MAX_FEED_PAGES {
+            let (feed, location) = self.fetch_feed(&url)?;
+            for entry in &feed.entries {
+                // Subfolders such as `!Extras` only have navigation links.
+                let link = match entry.acquisition() {
+                    Some(link) => link,
+                    None => continue,
+                };
+                let path = self.site_path(&resolve_url(&location, &link.href));
+                let filename = url_decode(path.rsplit('/').next().unwrap_or_default());
+                if filename.is_empty() || filename.starts_with('!') {
+                    continue;
+                }
+                let date_updated = parse_date(&entry.updated, listed_at);
+                let reader_path = format!("/reader{}", path);
+                files.extend(self.listed_file(
+                    &manga_title,
+                    &filename,
+                    reader_path,
+                    date_updated,
+                    link.length,
+                ));
+            }
+            match feed.link("next") {
+                Some(next) => url = resolve_url(&location, &next.href),
+                None => break,
+            }
+        }
+        Ok(files)
+    }
+}
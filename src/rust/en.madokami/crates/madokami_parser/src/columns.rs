@@ -0,0 +1,147 @@
+//! Finding a listing table's columns by their headers, so a reordered table
+//! is read correctly or reported as a layout change instead of yielding wrong
+//! dates and broken links.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{HtmlElement, SourceError, SourceResult};
+
+/// Header names for the column with each file or folder's link.
+pub const NAME_HEADERS: &[&str] = &["name", "file", "filename", "title", "series"];
+/// Header names for the column with each file's upload or modification time.
+pub const DATE_HEADERS: &[&str] = &[
+    "date",
+    "modified",
+    "last modified",
+    "uploaded",
+    "upload date",
+    "updated",
+    "time",
+];
+/// Header names for the column with each file's size.
+pub const SIZE_HEADERS: &[&str] = &["size", "file size"];
+/// Header names for the column with each file's web reader link.
+pub const READER_HEADERS: &[&str] = &["read", "reader", "online"];
+
+/// The header row of a listing table.
+#[derive(Debug, Clone, Default)]
+pub struct Columns {
+    /// Each column's header, lowercased, or empty when the table has none.
+    headers: Vec<String>,
+}
+
+/// The cells of a table row, in order.
+pub fn row_cells<E: HtmlElement>(row: &E) -> Vec<E> {
+    row.children()
+        .into_iter()
+        .filter(|cell| matches!(cell.name().as_str(), "td" | "th"))
+        .collect()
+}
+
+impl Columns {
+    /// Reads the headers of `table`, from its `thead` or else a first row made
+    /// of `th` cells.
+    pub fn read<E: HtmlElement>(table: &E) -> Columns {
+        let header_row = table.select_first("thead tr").or_else(|| {
+            table
+                .select_first("tr")
+                .filter(|row| row_cells(row).iter().all(|cell| cell.name() == "th"))
+        });
+        let headers = header_row
+            .map(|row| {
+                row_cells(&row)
+                    .iter()
+                    .map(|cell| cell.text().to_lowercase())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Columns { headers }
+    }
+
+    /// Whether the table has a header row at all.
+    pub fn has_headers(&self) -> bool {
+        self.headers.iter().any(|header| !header.is_empty())
+    }
+
+    /// The index of the first column whose header is one of `names`. Sort
+    /// arrows after the name are ignored, but the name must match whole, so
+    /// `File Size` isn't taken for `File`.
+    pub fn find(&self, names: &[&str]) -> Option<usize> {
+        self.headers.iter().position(|header| {
+            let header = header.trim_end_matches(|c: char| !c.is_alphanumeric());
+            names.contains(&header)
+        })
+    }
+
+    /// The index of the column whose header is one of `names`. Tables without
+    /// headers are assumed to have the layout Madokami has always used, with
+    /// the column at `usual`. When there are headers but none of them match,
+    /// the layout has changed and `missing` says how.
+    pub fn require(
+        &self,
+        names: &[&str],
+        usual: usize,
+        missing: &'static str,
+    ) -> SourceResult<usize> {
+        if !self.has_headers() {
+            return Ok(usual);
+        }
+        self.find(names).ok_or(SourceError::Layout(missing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlDocument;
+
+    #[test]
+    fn test_columns() {
+        let document = HtmlDocument::parse(
+            "<table><thead><tr><th>Uploaded ▼</th><th>Size</th><th>Name</th><th></th></tr></thead>\
+             <tbody><tr><td>today</td><td>1 MB</td><td><a href=\"/a\">a</a></td><td></td></tr></tbody></table>",
+        )
+        .root();
+        let columns = Columns::read(&document.select("table")[0]);
+        assert!(columns.has_headers());
+        assert_eq!(columns.find(NAME_HEADERS), Some(2));
+        assert_eq!(columns.find(DATE_HEADERS), Some(0));
+        assert_eq!(columns.find(SIZE_HEADERS), Some(1));
+        assert_eq!(columns.find(READER_HEADERS), None);
+        assert_eq!(columns.require(NAME_HEADERS, 0, "no name"), Ok(2));
+        assert_eq!(
+            columns.require(READER_HEADERS, 5, "no reader"),
+            Err(SourceError::Layout("no reader"))
+        );
+        // A header that only starts with the same letters isn't a match.
+        assert_eq!(
+            Columns::read(&HtmlDocument::parse("<table><tr><th>Sizes</th></tr></table>").root())
+                .find(SIZE_HEADERS),
+            None
+        );
+        // Nor is a header that only starts with another column's name.
+        let columns = Columns::read(
+            &HtmlDocument::parse(
+                "<table><tr><th>File Size ▲</th><th>Date</th><th>File</th></tr></table>",
+            )
+            .root(),
+        );
+        assert_eq!(columns.find(NAME_HEADERS), Some(2));
+        assert_eq!(columns.find(SIZE_HEADERS), Some(0));
+    }
+
+    #[test]
+    fn test_headerless_table() {
+        let document = HtmlDocument::parse(
+            "<table><tr><td><a href=\"/a\">a</a></td><td>1 MB</td></tr></table>",
+        )
+        .root();
+        let table = document.select("table").remove(0);
+        let columns = Columns::read(&table);
+        assert!(!columns.has_headers());
+        assert_eq!(columns.require(NAME_HEADERS, 0, "no name"), Ok(0));
+        let cells = row_cells(&table.select("tr")[0]);
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[1].text(), "1 MB");
+    }
+}
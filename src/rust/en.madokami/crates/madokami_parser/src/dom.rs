@@ -0,0 +1,61 @@
+//! The parts of a parsed page the site code reads, as traits. Live, pages are
+//! parsed by Aidoku's own HTML parser; the tests use the small reader in
+//! `html.rs` instead.
+
+use alloc::{string::String, vec::Vec};
+
+/// An element of a parsed page.
+pub trait HtmlElement: Sized {
+    /// The elements inside this one matching a CSS selector, in document
+    /// order.
+    fn select(&self, selector: &str) -> Vec<Self>;
+
+    /// The first element inside this one matching a selector.
+    fn select_first(&self, selector: &str) -> Option<Self> {
+        self.select(selector).into_iter().next()
+    }
+
+    /// The lowercased tag name.
+    fn name(&self) -> String;
+
+    /// An attribute's value, or an empty string when it isn't set.
+    fn attr(&self, name: &str) -> String;
+
+    /// Whether an attribute is set, even to an empty value.
+    fn has_attr(&self, name: &str) -> bool;
+
+    /// The element's text with runs of whitespace collapsed, the way browsers
+    /// show it.
+    fn text(&self) -> String;
+
+    /// The child elements, leaving out text.
+    fn children(&self) -> Vec<Self>;
+}
+
+/// Parses the pages the network fetched.
+pub trait ParseHtml {
+    type Element: HtmlElement;
+
+    /// Parses a page into its document element, or `None` when the page
+    /// can't be parsed at all.
+    fn parse_html(&self, html: &str) -> Option<Self::Element>;
+}
+
+/// The text of every element in a selection, joined with spaces.
+pub fn joined_text<E: HtmlElement>(elements: &[E]) -> String {
+    elements
+        .iter()
+        .map(|element| element.text())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The first value of an attribute among a selection, or an empty string.
+pub fn first_attr<E: HtmlElement>(elements: &[E], name: &str) -> String {
+    elements
+        .iter()
+        .find(|element| element.has_attr(name))
+        .map(|element| element.attr(name))
+        .unwrap_or_default()
+}
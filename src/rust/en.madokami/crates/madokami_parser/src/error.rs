@@ -0,0 +1,71 @@
+use alloc::{format, string::String};
+
+use crate::{format_size, ZipError, MAX_ARCHIVE_PAGES_SIZE};
+
+pub type SourceResult<T> = core::result::Result<T, SourceError>;
+
+/// Why a Madokami page couldn't be fetched or read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    /// Madokami answered with its login wall.
+    Unauthorized,
+    /// The path doesn't exist, usually because the folder was moved or renamed.
+    NotFound(String),
+    /// Madokami itself is failing (HTTP 5xx).
+    Server(i32),
+    /// Any other unexpected HTTP status.
+    Http(i32),
+    /// The page loaded but is missing something we rely on.
+    Layout(&'static str),
+    /// The chapter's archive couldn't be read directly.
+    Archive(ZipError),
+    /// The chapter is a file Aidoku can't show, such as a PDF or EPUB.
+    UnsupportedFormat(&'static str),
+    /// The chapter's archive holds more page data, in bytes, than can be read
+    /// directly.
+    ArchiveTooLarge(u64),
+}
+
+impl SourceError {
+    pub fn message(&self) -> String {
+        match self {
+            SourceError::Unauthorized => String::from(
+                "Madokami rejected the login, check the username and password in the source settings",
+            ),
+            SourceError::NotFound(path) => format!(
+                "{} was not found on Madokami, the folder may have been moved or renamed",
+                path
+            ),
+            SourceError::Server(code) => {
+                format!("Madokami is having trouble (HTTP {}), try again later", code)
+            }
+            SourceError::Http(code) => format!("Unexpected response from Madokami (HTTP {})", code),
+            SourceError::Layout(what) => format!("Madokami's page layout has changed: {}", what),
+            SourceError::Archive(error) => match error {
+                ZipError::Malformed => String::from("The chapter archive is damaged or not a ZIP file"),
+                ZipError::UnsupportedMethod(method) => format!(
+                    "The chapter archive uses an unsupported compression method ({})",
+                    method
+                ),
+                ZipError::Encrypted => String::from("The chapter archive is password protected"),
+                ZipError::Corrupt => String::from("A page in the chapter archive is corrupt"),
+            },
+            SourceError::UnsupportedFormat(format) => format!(
+                "{} files can't be read in Aidoku, download this chapter from Madokami instead",
+                format
+            ),
+            SourceError::ArchiveTooLarge(size) => format!(
+                "This chapter's archive holds {} of pages, and only archives of up to {} can be \
+                 read without Madokami's web reader. Download it from Madokami instead.",
+                format_size(*size),
+                format_size(MAX_ARCHIVE_PAGES_SIZE)
+            ),
+        }
+    }
+}
+
+impl From<ZipError> for SourceError {
+    fn from(error: ZipError) -> Self {
+        SourceError::Archive(error)
+    }
+}
@@ -0,0 +1,390 @@
+//! A small HTML reader for tests, standing in for Aidoku's parser so the site
+//! code can run on saved pages. It only reads well-formed markup like
+//! Madokami's, and only the selectors the parser uses: type, `#id`, `.class`
+//! and `[attr="value"]`, joined by descendant or `>` child combinators.
+
+use alloc::{format, rc::Rc, string::String, vec::Vec};
+
+use crate::{
+    decode_html_entities, parse_attributes, tag_end, FixtureNetwork, HtmlElement, ParseHtml,
+};
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+#[derive(Debug, Default)]
+struct Node {
+    /// The lowercased tag name, or empty for text.
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    parent: usize,
+    children: Vec<usize>,
+}
+
+/// A parsed HTML page.
+#[derive(Debug)]
+pub struct HtmlDocument {
+    nodes: Vec<Node>,
+}
+
+/// An element in an `HtmlDocument`.
+#[derive(Debug, Clone)]
+pub struct Element {
+    document: Rc<HtmlDocument>,
+    index: usize,
+}
+
+impl HtmlDocument {
+    /// Parses a page. Stray closing tags are ignored and unclosed elements
+    /// end with their parent.
+    pub fn parse(html: &str) -> HtmlDocument {
+        let mut document = HtmlDocument {
+            nodes: Vec::from([Node::default()]),
+        };
+        // The open elements, innermost last. The document itself is always open.
+        let mut open: Vec<usize> = Vec::from([0]);
+        let mut rest = html;
+
+        while !rest.is_empty() {
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                document.push_text(&open, decode_html_entities(&rest[..end]));
+                rest = &rest[end..];
+                continue;
+            }
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            let end = match tag_end(rest) {
+                Some(end) => end,
+                None => break,
+            };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with(['!', '?']) {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim().to_ascii_lowercase();
+                if let Some(depth) = open
+                    .iter()
+                    .rposition(|&index| document.nodes[index].name == name)
+                {
+                    open.truncate(depth.max(1));
+                }
+                continue;
+            }
+
+            let split = tag
+                .find(|c: char| c.is_whitespace() || c == '/')
+                .unwrap_or(tag.len());
+            let name = tag[..split].to_ascii_lowercase();
+            // Rows are put in a `tbody` when the table has none, as Aidoku's
+            // parser does, which is what the parser's `tbody tr` relies on.
+            if name == "tr" && document.nodes[open[open.len() - 1]].name == "table" {
+                let tbody = document.push_element(&open, String::from("tbody"), Vec::new());
+                open.push(tbody);
+            }
+            let index = document.push_element(&open, name.clone(), parse_attributes(&tag[split..]));
+            if name == "script" || name == "style" {
+                // Their content is left out of the tree, so markup in scripts
+                // isn't read as elements.
+                let close = rest.to_ascii_lowercase().find(&format!("</{}", name));
+                rest = close.map_or("", |close| &rest[close..]);
+            } else if !VOID_ELEMENTS.contains(&name.as_str()) && !tag.ends_with('/') {
+                open.push(index);
+            }
+        }
+        document
+    }
+
+    fn push_element(
+        &mut self,
+        open: &[usize],
+        name: String,
+        attributes: Vec<(String, String)>,
+    ) -> usize {
+        let parent = open.last().copied().unwrap_or(0);
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            name,
+            attributes,
+            text: String::new(),
+            parent,
+            children: Vec::new(),
+        });
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    fn push_text(&mut self, open: &[usize], text: String) {
+        if text.is_empty() {
+            return;
+        }
+        let index = self.push_element(open, String::new(), Vec::new());
+        self.nodes[index].text = text;
+    }
+
+    /// The document itself, above the `<html>` element.
+    pub fn root(self) -> Element {
+        Element {
+            document: Rc::new(self),
+            index: 0,
+        }
+    }
+}
+
+/// One step of a selector: an element's own requirements.
+#[derive(Debug, Default)]
+struct Compound<'s> {
+    name: Option<&'s str>,
+    id: Option<&'s str>,
+    classes: Vec<&'s str>,
+    attributes: Vec<(&'s str, &'s str)>,
+}
+
+/// A compound selector and whether it must be the direct parent of the next.
+type Step<'s> = (Compound<'s>, bool);
+
+fn parse_compound(input: &str) -> Option<Compound<'_>> {
+    let mut compound = Compound::default();
+    let name_end = input.find(['#', '.', '[']).unwrap_or(input.len());
+    if name_end > 0 {
+        compound.name = Some(&input[..name_end]);
+    }
+    let mut rest = &input[name_end..];
+    while let Some(kind) = rest.chars().next() {
+        let end = if kind == '[' {
+            rest.find(']')? + 1
+        } else {
+            rest[1..]
+                .find(['#', '.', '['])
+                .map_or(rest.len(), |end| end + 1)
+        };
+        let part = &rest[1..end];
+        match kind {
+            '#' => compound.id = Some(part),
+            '.' => compound.classes.push(part),
+            _ => {
+                let (name, value) = part.strip_suffix(']')?.split_once('=')?;
+                compound.attributes.push((name, value.trim_matches('"')));
+            }
+        }
+        rest = &rest[end..];
+    }
+    Some(compound)
+}
+
+/// Splits a selector into steps, outermost first. Attribute values in the
+/// parser's selectors have no spaces, so steps are split on whitespace.
+fn parse_selector(selector: &str) -> Option<Vec<Step<'_>>> {
+    let mut steps: Vec<Step<'_>> = Vec::new();
+    for part in selector.split_whitespace() {
+        if part == ">" {
+            steps.last_mut()?.1 = true;
+        } else {
+            steps.push((parse_compound(part)?, false));
+        }
+    }
+    (!steps.is_empty()).then_some(steps)
+}
+
+impl Element {
+    fn node(&self) -> &Node {
+        &self.document.nodes[self.index]
+    }
+
+    fn element(&self, index: usize) -> Element {
+        Element {
+            document: self.document.clone(),
+            index,
+        }
+    }
+
+    /// An attribute's value, without copying it.
+    fn attr_str(&self, name: &str) -> &str {
+        self.node()
+            .attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map_or("", |(_, value)| value.as_str())
+    }
+
+    fn parent(&self) -> Option<Element> {
+        (self.index != 0).then(|| self.element(self.node().parent))
+    }
+
+    /// Every element inside this one, in document order.
+    fn descendants(&self) -> Vec<Element> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.node().children.iter().rev().copied().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.document.nodes[index];
+            if !node.name.is_empty() {
+                found.push(self.element(index));
+                stack.extend(node.children.iter().rev());
+            }
+        }
+        found
+    }
+
+    fn matches(&self, compound: &Compound) -> bool {
+        compound.name.is_none_or(|name| self.node().name == name)
+            && compound.id.is_none_or(|id| self.attr_str("id") == id)
+            && compound.classes.iter().all(|class| {
+                self.attr_str("class")
+                    .split_whitespace()
+                    .any(|name| name == *class)
+            })
+            && compound
+                .attributes
+                .iter()
+                .all(|&(name, value)| self.has_attr(name) && self.attr_str(name) == value)
+    }
+
+    /// Whether this element and its ancestors, up to `scope`, satisfy the
+    /// steps of a selector, matching its last step against this element.
+    fn matches_steps(&self, steps: &[Step], scope: usize) -> bool {
+        let ((compound, _), outer) = match steps.split_last() {
+            Some(split) => split,
+            None => return true,
+        };
+        if !self.matches(compound) {
+            return false;
+        }
+        let direct = outer.last().is_some_and(|(_, child)| *child);
+        let mut ancestor = self.parent();
+        while let Some(element) = ancestor.filter(|element| element.index != scope) {
+            if element.matches_steps(outer, scope) {
+                return true;
+            }
+            if direct {
+                return false;
+            }
+            ancestor = element.parent();
+        }
+        outer.is_empty()
+    }
+
+    fn collect_text(&self, index: usize, output: &mut String) {
+        let node = &self.document.nodes[index];
+        output.push_str(&node.text);
+        for &child in &node.children {
+            self.collect_text(child, output);
+        }
+    }
+}
+
+impl HtmlElement for Element {
+    /// An unsupported selector matches nothing.
+    fn select(&self, selector: &str) -> Vec<Element> {
+        let steps = match parse_selector(selector) {
+            Some(steps) => steps,
+            None => return Vec::new(),
+        };
+        self.descendants()
+            .into_iter()
+            .filter(|element| element.matches_steps(&steps, self.index))
+            .collect()
+    }
+
+    fn name(&self) -> String {
+        self.node().name.clone()
+    }
+
+    fn attr(&self, name: &str) -> String {
+        String::from(self.attr_str(name))
+    }
+
+    fn has_attr(&self, name: &str) -> bool {
+        self.node()
+            .attributes
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    /// The element's text with runs of whitespace collapsed, as Aidoku's
+    /// parser gives it.
+    fn text(&self) -> String {
+        let mut raw = String::new();
+        self.collect_text(self.index, &mut raw);
+        let mut text = String::with_capacity(raw.len());
+        for word in raw.split_whitespace() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(word);
+        }
+        text
+    }
+
+    fn children(&self) -> Vec<Element> {
+        self.node()
+            .children
+            .iter()
+            .filter(|&&index| !self.document.nodes[index].name.is_empty())
+            .map(|&index| self.element(index))
+            .collect()
+    }
+}
+
+/// The fixture network's pages are read with `HtmlDocument`.
+impl ParseHtml for FixtureNetwork {
+    type Element = Element;
+
+    fn parse_html(&self, html: &str) -> Option<Element> {
+        Some(HtmlDocument::parse(html).root())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{first_attr, joined_text};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Index of /Manga - Madokami</title>
+<script>if (a < b && c > d) { document.write("<td>") }</script></head>
+<body>
+<div class="container">
+  <table id="index-table" class="table mobile-files-table">
+    <tbody>
+      <tr><td><a href="/a">First &amp; one</a></td><td>12 MB</td></tr>
+      <tr><td><a href="/b">Second</a> <a href="/c">extra</a></td><td>1 MB</td></tr>
+    </tbody>
+  </table>
+  <input type="password" name="password">
+  <img src="/cover.jpg" itemprop="image"/>
+</div>
+</body></html>"#;
+
+    #[test]
+    fn test_select() {
+        let document = HtmlDocument::parse(PAGE).root();
+        let rows = document.select("table#index-table > tbody > tr");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].select("a")[0].text(), "First & one");
+        assert_eq!(joined_text(&rows[1].select("td a")), "Second extra");
+        assert_eq!(rows[1].children().len(), 2);
+        assert_eq!(document.select("div.container table tbody tr").len(), 2);
+        assert_eq!(document.select("table.mobile-files-table").len(), 1);
+        assert_eq!(document.select("input[type=\"password\"]").len(), 1);
+        assert_eq!(
+            first_attr(&document.select("img[itemprop=\"image\"]"), "src"),
+            "/cover.jpg"
+        );
+        assert_eq!(
+            document.select("title")[0].text(),
+            "Index of /Manga - Madokami"
+        );
+        // Markup in scripts isn't read, and child steps must be direct.
+        assert!(document.select("script td").is_empty());
+        assert!(document.select("div > tr").is_empty());
+        let bare = HtmlDocument::parse("<table><tr><td>a</td></tr></table>").root();
+        assert_eq!(bare.select("table > tbody > tr").len(), 1);
+    }
+}
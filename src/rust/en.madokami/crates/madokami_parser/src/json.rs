@@ -0,0 +1,88 @@
+//! Reads the JSON Madokami's reader page embeds in its attributes.
+
+use alloc::{string::String, vec::Vec};
+
+/// Reads a JSON array of strings, such as the reader's file list.
+pub fn parse_string_array(json: &str) -> Option<Vec<String>> {
+    let mut rest = json.trim().strip_prefix('[')?.trim_start();
+    let mut strings = Vec::new();
+    if let Some(after) = rest.strip_prefix(']') {
+        return after.trim().is_empty().then_some(strings);
+    }
+    loop {
+        let mut chars = rest.strip_prefix('"')?.char_indices();
+        let mut value = String::new();
+        let end = loop {
+            let (index, c) = chars.next()?;
+            match c {
+                '"' => break index + 1,
+                '\\' => {
+                    let (_, escape) = chars.next()?;
+                    value.push(match escape {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let hex = |chars: &mut core::str::CharIndices| {
+                                let digits: String =
+                                    chars.by_ref().take(4).map(|(_, c)| c).collect();
+                                u32::from_str_radix(&digits, 16).ok()
+                            };
+                            let high = hex(&mut chars)?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                let low = chars
+                                    .as_str()
+                                    .strip_prefix("\\u")
+                                    .and_then(|after| u32::from_str_radix(after.get(..4)?, 16).ok())
+                                    .filter(|low| (0xDC00..0xE000).contains(low));
+                                match low {
+                                    Some(low) => {
+                                        chars.nth(5);
+                                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                                    }
+                                    None => 0xFFFD,
+                                }
+                            } else {
+                                high
+                            };
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        other => other,
+                    });
+                }
+                c => value.push(c),
+            }
+        };
+        strings.push(value);
+        rest = rest[1 + end..].trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after.trim_start();
+        } else {
+            let after = rest.strip_prefix(']')?;
+            return after.trim().is_empty().then_some(strings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_array() {
+        assert_eq!(
+            parse_string_array(r#"["01.jpg", "a \"b\"\\c", "é😀"]"#),
+            Some(Vec::from([
+                String::from("01.jpg"),
+                String::from("a \"b\"\\c"),
+                String::from("é😀"),
+            ]))
+        );
+        assert_eq!(parse_string_array("[]"), Some(Vec::new()));
+        assert_eq!(parse_string_array("[\"a\""), None);
+        assert_eq!(parse_string_array("{}"), None);
+        assert_eq!(parse_string_array("[1]"), None);
+    }
+}
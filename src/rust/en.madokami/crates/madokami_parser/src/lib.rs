@@ -0,0 +1,24 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod catalog;
+mod columns;
+mod dom;
+mod error;
+#[cfg(test)]
+mod html;
+mod json;
+mod links;
+mod site;
+mod store;
+pub use columns::*;
+pub use dom::*;
+pub use error::*;
+#[cfg(test)]
+pub use html::*;
+pub use json::*;
+pub use links::*;
+pub use site::*;
+pub use source_common::*;
+pub use store::*;
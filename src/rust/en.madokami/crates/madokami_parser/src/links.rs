@@ -0,0 +1,158 @@
+use alloc::{format, string::String};
+
+use crate::{encode_path, is_chapter_file, query_param, url_decode};
+
+/// What a Madokami URL points at, with its paths decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MadokamiLink {
+    /// A folder, such as a series.
+    Folder(String),
+    /// A search, from a `/search?q=` link.
+    Search(String),
+    /// A chapter archive, from a reader, image or download link.
+    Chapter(String),
+}
+
+impl MadokamiLink {
+    /// The manga ID for the folder the link belongs to. Searches have none.
+    pub fn manga_id(&self) -> String {
+        match self {
+            MadokamiLink::Folder(path) => encode_path(path),
+            MadokamiLink::Search(_) => String::new(),
+            MadokamiLink::Chapter(archive) => {
+                encode_path(archive.rsplit_once('/').map_or("", |(folder, _)| folder))
+            }
+        }
+    }
+
+    /// The chapter ID for chapter links, as the reader link in a folder listing.
+    pub fn chapter_id(&self) -> Option<String> {
+        match self {
+            MadokamiLink::Folder(_) | MadokamiLink::Search(_) => None,
+            MadokamiLink::Chapter(archive) => Some(format!("/reader{}", encode_path(archive))),
+        }
+    }
+}
+
+/// Works out what a Madokami URL points at. `url` is the path and query,
+/// without the scheme and host.
+///
+/// Reader links are `/reader/<archive path>`, shared images are
+/// `/reader/image?path=<archive path>&file=<image>`, and downloads are the
+/// archive path itself. Aidoku can only open a chapter, not a page of it, so
+/// page numbers and image names in reader links are ignored.
+pub fn parse_link(url: &str) -> Option<MadokamiLink> {
+    let url = url.split('#').next().unwrap_or_default();
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = path.trim_end_matches('/');
+    if path.is_empty() {
+        return None;
+    }
+
+    let archive = if path == "/search" {
+        let search = query_param(query, "q")?;
+        let search = search.trim();
+        return (!search.is_empty()).then(|| MadokamiLink::Search(String::from(search)));
+    } else if path == "/reader/image" {
+        query_param(query, "path")?
+    } else if let Some(archive) = path.strip_prefix("/reader/") {
+        url_decode(archive)
+    } else if is_chapter_file(path) {
+        url_decode(path)
+    } else {
+        return Some(MadokamiLink::Folder(url_decode(path)));
+    };
+    let archive = format!("/{}", archive.trim_matches('/'));
+    if archive.len() < 2 {
+        return None;
+    }
+    Some(MadokamiLink::Chapter(archive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARCHIVE: &str = "/Manga/B/BE/BERS/Berserk/Berserk v01 (2003) [Digital].cbz";
+
+    fn chapter() -> Option<MadokamiLink> {
+        Some(MadokamiLink::Chapter(String::from(ARCHIVE)))
+    }
+
+    #[test]
+    fn test_reader_links() {
+        let reader =
+            "/reader/Manga/B/BE/BERS/Berserk/Berserk%20v01%20%282003%29%20%5BDigital%5D.cbz";
+        assert_eq!(parse_link(reader), chapter());
+        assert_eq!(parse_link(&format!("{}?ch=1", reader)), chapter());
+        assert_eq!(parse_link(&format!("{}?page=12", reader)), chapter());
+        assert_eq!(parse_link(&format!("{}#7", reader)), chapter());
+        assert_eq!(
+            parse_link(&format!("{}?file=Berserk+v01%2F012.jpg", reader)),
+            chapter()
+        );
+
+        let link = parse_link(reader).unwrap();
+        assert_eq!(link.chapter_id().as_deref(), Some(reader));
+        assert_eq!(link.manga_id(), "/Manga/B/BE/BERS/Berserk");
+    }
+
+    #[test]
+    fn test_image_links() {
+        let image = "/reader/image?path=%2FManga%2FB%2FBE%2FBERS%2FBerserk%2FBerserk%20v01%20%282003%29%20%5BDigital%5D.cbz&file=012.jpg";
+        assert_eq!(parse_link(image), chapter());
+        assert_eq!(parse_link("/reader/image?file=012.jpg"), None);
+        assert_eq!(parse_link("/reader/image?path=&file=012.jpg"), None);
+    }
+
+    #[test]
+    fn test_download_links() {
+        let download = "/Manga/B/BE/BERS/Berserk/Berserk%20v01%20%282003%29%20%5BDigital%5D.cbz";
+        assert_eq!(parse_link(download), chapter());
+        assert_eq!(
+            parse_link(download).unwrap().chapter_id().unwrap(),
+            format!("/reader{}", download)
+        );
+        assert_eq!(
+            parse_link("/Manga/B/BE/BERS/Berserk/!Extras/Berserk%20Guide.pdf")
+                .unwrap()
+                .manga_id(),
+            "/Manga/B/BE/BERS/Berserk/%21Extras"
+        );
+        assert_eq!(
+            parse_link("/Manga/K/KA/KAIJ/Kaiju%20No.%208"),
+            Some(MadokamiLink::Folder(String::from(
+                "/Manga/K/KA/KAIJ/Kaiju No. 8"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_search_links() {
+        assert_eq!(
+            parse_link("/search?q=shingeki+no%20kyojin"),
+            Some(MadokamiLink::Search(String::from("shingeki no kyojin")))
+        );
+        assert_eq!(parse_link("/search?q=+"), None);
+        assert_eq!(parse_link("/search"), None);
+        assert_eq!(parse_link("/search?q=berserk").unwrap().chapter_id(), None);
+    }
+
+    #[test]
+    fn test_folder_links() {
+        assert_eq!(
+            parse_link("/Manga/S/SH/SHIN/Shingeki%20no%20Kyojin/"),
+            Some(MadokamiLink::Folder(String::from(
+                "/Manga/S/SH/SHIN/Shingeki no Kyojin"
+            )))
+        );
+        assert_eq!(
+            parse_link("/Manga/P/PO/POKE/Pok%C3%A9mon")
+                .unwrap()
+                .manga_id(),
+            "/Manga/P/PO/POKE/Pok%C3%A9mon"
+        );
+        assert_eq!(parse_link("/"), None);
+        assert_eq!(parse_link(""), None);
+    }
+}
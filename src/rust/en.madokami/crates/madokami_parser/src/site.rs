@@ -0,0 +1,1624 @@
+//! What the source's handlers do, written against the `Network` trait so it
+//! runs the same on the live site and on saved pages. The Aidoku crate only
+//! reads the settings, sends the requests and converts the results.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    archive_pages, canonical_path, clean_description, clean_filename, extract_manga_title,
+    file_kind, first_attr, format_size, get_parent_path, joined_text, page_files, parse_date,
+    parse_filename, parse_link, parse_size, parse_string_array, row_cells, same_host, url_decode,
+    url_encode, ArchiveError, ChapterIdStore, ChapterInfo, Columns, FileKind, HtmlElement,
+    MadokamiLink, MangaInfo, Network, PageInfo, PageSource, ParseHtml, SourceError, SourceResult,
+    DATE_HEADERS, HTML_ACCEPT, NAME_HEADERS, READER_HEADERS, SIZE_HEADERS, UNKNOWN_DATE,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://manga.madokami.al";
+/// How many redirects are followed before giving up.
+const MAX_REDIRECTS: u32 = 5;
+
+/// Where search results, browsing and chapter lists come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Madokami's HTML pages.
+    Website,
+    /// Madokami's OPDS catalog, which keeps working when the page layout changes.
+    Opds,
+}
+
+impl Backend {
+    /// The backend for the `backend` setting's value.
+    pub fn from_setting(value: &str) -> Backend {
+        if value == "opds" {
+            Backend::Opds
+        } else {
+            Backend::Website
+        }
+    }
+}
+
+/// The Madokami URL to use for the `baseUrl` setting's value, so a mirror or
+/// caching proxy can be used when the domain moves. Falls back to
+/// `DEFAULT_BASE_URL`.
+pub fn configured_base_url(value: &str) -> String {
+    let url = value.trim().trim_end_matches('/');
+    if url.starts_with("https://") || url.starts_with("http://") {
+        String::from(url)
+    } else {
+        String::from(DEFAULT_BASE_URL)
+    }
+}
+
+/// The source settings the handlers depend on.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub base_url: String,
+    pub backend: Backend,
+    /// Whether scanlator credit and recruitment pages are left out of chapters.
+    pub skip_credit_pages: bool,
+    /// Whether only the smallest of several files for the same chapter is
+    /// listed, to save mobile data.
+    pub smallest_duplicates: bool,
+    /// Whether filename parsing is explained in the log.
+    pub parser_debug: bool,
+    /// The current time, which relative dates and undated files are read as.
+    pub now: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            base_url: String::from(DEFAULT_BASE_URL),
+            backend: Backend::Website,
+            skip_credit_pages: false,
+            smallest_duplicates: false,
+            parser_debug: false,
+            now: 0.0,
+        }
+    }
+}
+
+/// Where a link points, as Aidoku's `DeepLink`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkTarget {
+    pub manga: MangaInfo,
+    pub chapter_id: Option<String>,
+}
+
+/// Madokami, reached through `N`.
+pub struct Madokami<N: Network> {
+    pub(crate) network: N,
+    pub(crate) settings: Settings,
+    log: RefCell<Vec<String>>,
+}
+
+/// A file in a folder listing, before it's given a chapter ID.
+pub(crate) struct ListedFile {
+    chapter: ChapterInfo,
+    /// The reader path, which is also the chapter ID used before IDs were
+    /// keyed on the chapter number.
+    reader_path: String,
+    keys: Vec<String>,
+    /// The key the file's chapter ID was given for, once IDs are assigned.
+    key: Option<String>,
+    /// The file's size in bytes, when the listing shows it.
+    size: Option<u64>,
+    /// The first and last chapter of a pack whose name gives them.
+    range: Option<(f32, f32)>,
+}
+
+/// Whether another file for the same chapter, or the same range of chapters,
+/// is smaller, with ties going to the file with the lower ID.
+fn larger_duplicate(file: &ListedFile, files: &[ListedFile]) -> bool {
+    let (key, size) = match (file.keys.first(), file.size) {
+        (Some(key), Some(size)) => (key, size),
+        _ => return false,
+    };
+    files.iter().any(|other| {
+        other.keys.first() == Some(key)
+            && other.range == file.range
+            && other.size.is_some_and(|other_size| {
+                other_size < size || (other_size == size && other.chapter.id < file.chapter.id)
+            })
+    })
+}
+
+/// Whether a pack is covered by smaller chapter files. Only packs whose name
+/// gives their chapters, like `v01 c001-008`, can be: every chapter from the
+/// first to the last must have a file that shows its size. Parts and extras
+/// don't count, since a chapter with one part listed may be missing another.
+fn covered_pack(file: &ListedFile, files: &[&ListedFile]) -> bool {
+    let ((first, last), size) = match (file.range, file.size) {
+        (Some(range), Some(size)) if is_whole(range.0) && is_whole(range.1) => (range, size),
+        _ => return false,
+    };
+    let chapters = || {
+        files.iter().filter(move |other| {
+            let chapter = other.chapter.chapter;
+            other.range.is_none() && is_whole(chapter) && (first..=last).contains(&chapter)
+        })
+    };
+    let mut numbers: Vec<i64> = chapters()
+        .map(|other| other.chapter.chapter as i64)
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    let total = chapters().map(|other| other.size).sum::<Option<u64>>();
+    numbers.len() as i64 == last as i64 - first as i64 + 1
+        && total.is_some_and(|total| total < size)
+}
+
+/// Whether a chapter number has no fraction, i.e. isn't a part or an extra.
+fn is_whole(chapter: f32) -> bool {
+    chapter == (chapter as i64) as f32
+}
+
+/// Tells apart the login wall, missing paths and server failures by status code.
+pub fn check_status(code: i32, url: &str) -> SourceResult<()> {
+    match code {
+        200..=399 => Ok(()),
+        401 | 403 => Err(SourceError::Unauthorized),
+        404 | 410 => Err(SourceError::NotFound(String::from(url))),
+        500..=599 => Err(SourceError::Server(code)),
+        _ => Err(SourceError::Http(code)),
+    }
+}
+
+/// The folder a Madokami listing shows, from its `Index of /path` title.
+fn listing_path<E: HtmlElement>(html: &E) -> Option<String> {
+    let title = html.select_first("title")?.text();
+    let path = title.trim().strip_prefix("Index of ")?;
+    let path = path.strip_suffix(" - Madokami").unwrap_or(path);
+    path.starts_with('/').then(|| canonical_path(path))
+}
+
+/// Where the path starts in a URL with its scheme removed.
+fn url_path_start(rest: &str) -> usize {
+    rest.find(['/', '?', '#']).unwrap_or(rest.len())
+}
+
+/// The chapter ID for a file, keyed on its chapter number so it survives the
+/// file being renamed or re-uploaded.
+fn keyed_chapter_id(folder: &str, key: &str) -> String {
+    format!("{}?ch={}", folder, key)
+}
+
+/// The folder and chapter key of an old reader path ID, for finding its file
+/// after it has been renamed.
+fn legacy_chapter_key(reader_path: &str) -> Option<(String, String)> {
+    let archive = url_decode(reader_path.strip_prefix("/reader")?);
+    let (folder, filename) = archive.rsplit_once('/')?;
+    let folder = canonical_path(folder);
+    let info = parse_filename(&clean_filename(filename), &extract_manga_title(&folder));
+    let key = info.chapter_keys().into_iter().next()?;
+    Some((folder, key))
+}
+
+/// The download link for a chapter, from its `/reader/...` ID.
+pub fn download_path(chapter_id: &str) -> String {
+    String::from(chapter_id.strip_prefix("/reader").unwrap_or(chapter_id))
+}
+
+impl<N: Network + ParseHtml + ChapterIdStore> Madokami<N> {
+    pub fn new(network: N, settings: Settings) -> Self {
+        Madokami {
+            network,
+            settings,
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.settings.base_url
+    }
+
+    /// The diagnostics written since the last call, for the Aidoku log.
+    pub fn take_log(&self) -> Vec<String> {
+        self.log.take()
+    }
+
+    /// Strips the scheme and host from a Madokami URL, accepting both the
+    /// default host and the configured mirror.
+    pub fn strip_base_url(&self, url: &str) -> String {
+        match url.split_once("://") {
+            Some((_, rest)) if self.is_madokami_url(url) => {
+                let path = &rest[url_path_start(rest)..];
+                // A mirror served under a path keeps that path out of IDs.
+                let mirror_path = self
+                    .base_url()
+                    .split_once("://")
+                    .map_or("", |(_, base)| &base[url_path_start(base)..]);
+                let path = match path.strip_prefix(mirror_path) {
+                    Some(rest) if same_host(url, self.base_url()) => rest,
+                    _ => path,
+                };
+                String::from(path)
+            }
+            _ => String::from(url),
+        }
+    }
+
+    /// Whether an absolute URL is on the default Madokami host or the
+    /// configured mirror.
+    fn is_madokami_url(&self, url: &str) -> bool {
+        same_host(url, self.base_url()) || same_host(url, DEFAULT_BASE_URL)
+    }
+
+    /// Where a redirect points, if it stays on the Madokami host.
+    fn redirect_target(&self, location: &str) -> Option<String> {
+        let location = location.trim();
+        if location.starts_with('/') {
+            Some(format!("{}{}", self.base_url(), location))
+        } else if same_host(location, self.base_url()) {
+            Some(String::from(location))
+        } else {
+            None
+        }
+    }
+
+    /// Fetches the body of a Madokami URL, following redirects on the Madokami
+    /// host. Returns the body along with the URL it was found at.
+    pub(crate) fn fetch_body(&self, url: &str, accept: &str) -> SourceResult<(Vec<u8>, String)> {
+        let mut location = String::from(url);
+        let mut redirects = 0;
+        let response = loop {
+            let response = self.network.get(&location, &[("Accept", accept)]);
+            let code = response.status;
+            if !matches!(code, 301 | 302 | 303 | 307 | 308) || redirects >= MAX_REDIRECTS {
+                check_status(code, &location)?;
+                break response;
+            }
+            location = response
+                .header("Location")
+                .and_then(|target| self.redirect_target(target))
+                .ok_or(SourceError::Http(code))?;
+            redirects += 1;
+        };
+        Ok((response.body, location))
+    }
+
+    /// Fetches a Madokami page and parses it, following redirects and
+    /// reporting the login wall as `Unauthorized`. Returns the page along with
+    /// the URL it was found at, which differs from `url` when a folder has been
+    /// renamed. Recently fetched pages are served from the network's cache.
+    pub(crate) fn fetch_page(&self, url: &str) -> SourceResult<(N::Element, String)> {
+        if let Some((data, location)) = self.network.cached(url) {
+            return Ok((self.parse_page(&data)?, location));
+        }
+        let (data, location) = self.fetch_body(url, HTML_ACCEPT)?;
+        let html = self.parse_page(&data)?;
+        // Networks that follow redirects themselves hide the move, but the
+        // listing still names the folder it shows.
+        let location = listing_path(&html)
+            .map(|path| format!("{}{}", self.base_url(), path))
+            .unwrap_or(location);
+        if html.select("input[type=\"password\"]").is_empty() {
+            self.network.store(url, &location, &data);
+            Ok((html, location))
+        } else {
+            Err(SourceError::Unauthorized)
+        }
+    }
+
+    fn parse_page(&self, data: &[u8]) -> SourceResult<N::Element> {
+        self.network
+            .parse_html(&String::from_utf8_lossy(data))
+            .ok_or(SourceError::Layout("the page couldn't be parsed"))
+    }
+
+    /// Fetches a Madokami page and parses it, like `fetch_page`.
+    pub(crate) fn fetch_html(&self, url: &str) -> SourceResult<N::Element> {
+        self.fetch_page(url).map(|(html, _)| html)
+    }
+
+    /// Checks whether Madokami lets the stored credentials in, by fetching the
+    /// home page the way every other page is fetched. A login form served
+    /// with a success status counts as a rejection too.
+    pub fn check_login(&self) -> SourceResult<()> {
+        self.fetch_page(self.base_url()).map(|_| ())
+    }
+
+    /// The series and chapter a Madokami link points at.
+    pub fn handle_url(&self, url: &str) -> SourceResult<LinkTarget> {
+        // Links to other sites are never Madokami's, whatever their path.
+        if url.contains("://") && !self.is_madokami_url(url) {
+            return Err(SourceError::NotFound(String::from(url)));
+        }
+        // Remove the base URL from the passed in URL so we work only with the path.
+        let url = self.strip_base_url(url);
+        let link = parse_link(&url).ok_or_else(|| SourceError::NotFound(url.clone()))?;
+        if let MadokamiLink::Search(query) = &link {
+            return Ok(LinkTarget {
+                manga: self.search_link_result(query)?,
+                chapter_id: None,
+            });
+        }
+        // Nested folders such as `!Extras` belong to the series above them.
+        let folder = link.manga_id();
+        let manga_id = canonical_path(&get_parent_path(&folder).unwrap_or(folder));
+        // Aidoku deep links can't pick a starting page, so a page or file in the
+        // link only narrows it down to its chapter.
+        let chapter_id = link.chapter_id().map(|reader_path| {
+            // Use the chapter's keyed ID from its folder listing when possible.
+            self.folder_files(&link.manga_id(), false)
+                .ok()
+                .and_then(|files| {
+                    files
+                        .into_iter()
+                        .find(|file| file.reader_path == reader_path)
+                })
+                .map_or(reader_path, |file| file.chapter.id)
+        });
+        Ok(LinkTarget {
+            manga: MangaInfo {
+                id: manga_id,
+                ..Default::default()
+            },
+            chapter_id,
+        })
+    }
+
+    /// The series a search link most likely means. Deep links can't open a
+    /// search, so this is the result whose title matches the query, or else
+    /// the first one.
+    fn search_link_result(&self, query: &str) -> SourceResult<MangaInfo> {
+        let (mut results, _) = self.manga_list(Some(query), 1)?;
+        let exact = results
+            .iter()
+            .position(|manga| manga.title.eq_ignore_ascii_case(query));
+        match exact {
+            Some(index) => Ok(results.swap_remove(index)),
+            None if !results.is_empty() => Ok(results.swap_remove(0)),
+            None => Err(SourceError::NotFound(format!(
+                "/search?q={}",
+                url_encode(query)
+            ))),
+        }
+    }
+
+    /// Lists the series matching `query`, or the recently updated ones without
+    /// a query, from the chosen backend. Also says whether there is another
+    /// page.
+    pub fn manga_list(
+        &self,
+        query: Option<&str>,
+        page: i32,
+    ) -> SourceResult<(Vec<MangaInfo>, bool)> {
+        if self.settings.backend == Backend::Opds {
+            return self.opds_manga_list(query, page);
+        }
+        // The website shows everything on one page.
+        if page > 1 {
+            return Ok((Vec::new(), false));
+        }
+        let url = match query {
+            Some(query) => format!("{}/search?q={}", self.base_url(), url_encode(query)),
+            None => format!("{}/recent", self.base_url()),
+        };
+        Ok((self.website_manga_list(&url)?, false))
+    }
+
+    /// Lists the series on a search results or recent uploads page.
+    fn website_manga_list(&self, url: &str) -> SourceResult<Vec<MangaInfo>> {
+        let html = self.fetch_html(url)?;
+        let (selector, missing) = if url.ends_with("/recent") {
+            (
+                "table.mobile-files-table",
+                "the recent uploads table has no Name column",
+            )
+        } else {
+            (
+                "div.container table",
+                "the search results table has no Name column",
+            )
+        };
+
+        let mut mangas = Vec::new();
+        for table in html.select(selector) {
+            let name_column = Columns::read(&table).require(NAME_HEADERS, 0, missing)?;
+            for row in table.select("tbody tr") {
+                let link = match row_cells(&row)
+                    .get(name_column)
+                    .and_then(|cell| cell.select_first("a"))
+                {
+                    Some(link) => link,
+                    None => continue,
+                };
+                let path = link.attr("href");
+                if path.trim().is_empty() {
+                    continue;
+                }
+                let id = canonical_path(&path);
+                mangas.push(MangaInfo {
+                    title: extract_manga_title(&id),
+                    url: format!("{}{}", self.base_url(), id),
+                    id,
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(mangas)
+    }
+
+    /// Reads a file in a series folder as a chapter, or `None` for notes and
+    /// metadata that aren't chapters.
+    pub(crate) fn listed_file(
+        &self,
+        manga_title: &str,
+        filename: &str,
+        reader_path: String,
+        date_updated: Option<f64>,
+        size: Option<u64>,
+    ) -> Option<ListedFile> {
+        let format = match file_kind(filename) {
+            FileKind::Text => return None,
+            FileKind::Document(format) => Some(format),
+            FileKind::Pages => None,
+        };
+        let name = clean_filename(filename);
+        let info = parse_filename(&name, manga_title);
+        if self.settings.parser_debug {
+            self.log.borrow_mut().push(info.explain());
+        }
+
+        // Parts of a split chapter get their own number, so label the ones
+        // whose name only hints at it, like `c012-1`.
+        let mut title = match info.part {
+            Some(part) if info.part_attached => format!("{} (Part {})", name, part),
+            _ => name,
+        };
+        // Documents stay listed so they can be found, but can't be opened.
+        if let Some(format) = format {
+            title.push_str(&format!(" [{}]", format));
+        }
+
+        // The size goes on the scanlator line, so the title stays the filename.
+        let scanlator = match (info.group.clone(), size) {
+            (Some(group), Some(size)) => format!("{} · {}", group, format_size(size)),
+            (Some(group), None) => group,
+            (None, Some(size)) => format_size(size),
+            (None, None) => String::new(),
+        };
+
+        Some(ListedFile {
+            keys: info.chapter_keys(),
+            key: None,
+            size,
+            range: info.chapter_range(),
+            chapter: ChapterInfo {
+                title,
+                chapter: info.ordered_chapter().unwrap_or(-1.0),
+                volume: info.volume.unwrap_or(-1.0),
+                date_updated: date_updated.unwrap_or(UNKNOWN_DATE),
+                scanlator,
+                url: format!("{}{}", self.base_url(), reader_path),
+                ..Default::default()
+            },
+            reader_path,
+        })
+    }
+
+    /// Lists the files in a Madokami folder from its page on the website.
+    fn website_folder_files(&self, id: &str) -> SourceResult<Vec<ListedFile>> {
+        let url = format!("{}{}", self.base_url(), id);
+        let html = self.fetch_html(&url)?;
+        let table = html
+            .select_first("table#index-table")
+            .ok_or(SourceError::Layout("the folder page has no file table"))?;
+        let columns = Columns::read(&table);
+        let name_column =
+            columns.require(NAME_HEADERS, 0, "the folder table has no Name column")?;
+        let date_column =
+            columns.require(DATE_HEADERS, 2, "the folder table has no Date column")?;
+        let reader_column = columns.find(READER_HEADERS);
+        // Sizes are only shown, so a missing Size column isn't a layout change.
+        let size_column = if columns.has_headers() {
+            columns.find(SIZE_HEADERS)
+        } else {
+            Some(1)
+        };
+        let manga_title = extract_manga_title(id);
+        let listed_at = self.settings.now;
+        let mut files = Vec::new();
+
+        for node in table.select("tbody > tr") {
+            let cells = row_cells(&node);
+            let name_cell = match cells.get(name_column) {
+                Some(cell) => cell,
+                None => continue,
+            };
+            let title = joined_text(&name_cell.select("a"));
+            if title.is_empty() || title.ends_with('/') || title.starts_with('!') {
+                continue;
+            }
+            // The reader link is wherever the row has one, and otherwise the
+            // download link is opened in the reader.
+            let reader_link = match reader_column {
+                Some(index) => cells.get(index).and_then(|cell| cell.select_first("a")),
+                None => node
+                    .select("a")
+                    .into_iter()
+                    .find(|link| link.attr("href").contains("/reader/")),
+            };
+            let reader_href = reader_link
+                .map(|link| link.attr("href"))
+                .unwrap_or_default();
+            let reader_path = match reader_href.split_once("/reader/") {
+                Some((_, reader_part)) => format!("/reader/{}", reader_part),
+                None => match name_cell.select_first("a") {
+                    Some(link) if self.strip_base_url(&link.attr("href")).starts_with('/') => {
+                        format!("/reader{}", self.strip_base_url(&link.attr("href")))
+                    }
+                    _ => continue,
+                },
+            };
+            // Fall back to the full timestamp in the cell's tooltip.
+            let date_updated = cells.get(date_column).and_then(|cell| {
+                parse_date(&cell.text(), listed_at)
+                    .or_else(|| parse_date(&cell.attr("title"), listed_at))
+            });
+            let size = size_column
+                .and_then(|index| cells.get(index))
+                .and_then(|cell| parse_size(&cell.text()));
+            let filename = url_decode(&title);
+            files.extend(self.listed_file(
+                &manga_title,
+                &filename,
+                reader_path,
+                date_updated,
+                size,
+            ));
+        }
+        Ok(files)
+    }
+
+    /// Lists every chapter file in a Madokami folder from the chosen backend,
+    /// newest first, with their chapter IDs.
+    ///
+    /// Each file is given an ID for one of its chapter keys. A file keeps the
+    /// ID it was given before, even after a rename. Files that were already
+    /// listed when IDs were first kept get their reader path, which is the ID
+    /// Aidoku's history has for them, and only newer files get a keyed ID.
+    /// The IDs are only kept with `keep_ids`, when the chapters are listed,
+    /// so looking a file up doesn't keep IDs for every folder a link points
+    /// into.
+    fn folder_files(&self, id: &str, keep_ids: bool) -> SourceResult<Vec<ListedFile>> {
+        let id = canonical_path(id);
+        let mut files = match self.settings.backend {
+            Backend::Website => self.website_folder_files(&id)?,
+            Backend::Opds => self.opds_folder_files(&id)?,
+        };
+
+        // The oldest file gets the plainest key, so a newer duplicate of a
+        // chapter can't take over the ID of one already being read. Files
+        // without a date come last, in listing order.
+        let mut oldest_first: Vec<usize> = (0..files.len()).collect();
+        oldest_first.sort_by(|&a, &b| {
+            let (a, b) = (files[a].chapter.date_updated, files[b].chapter.date_updated);
+            (a == UNKNOWN_DATE)
+                .cmp(&(b == UNKNOWN_DATE))
+                .then(a.total_cmp(&b))
+        });
+        let kept = self.network.chapter_ids(&id);
+        let first_listing = kept.is_none();
+        let mut known = kept.unwrap_or_default();
+        let known_count = known.len();
+        let mut used_keys: Vec<String> = Vec::new();
+        for index in oldest_first {
+            let file = &mut files[index];
+            let key = match file.keys.iter().find(|key| !used_keys.contains(key)) {
+                Some(key) => key.clone(),
+                None => {
+                    file.chapter.id = file.reader_path.clone();
+                    continue;
+                }
+            };
+            file.chapter.id = match known.iter().find(|(known_key, _)| *known_key == key) {
+                Some((_, chapter_id)) => chapter_id.clone(),
+                None => {
+                    let chapter_id = if first_listing {
+                        file.reader_path.clone()
+                    } else {
+                        keyed_chapter_id(&id, &key)
+                    };
+                    known.push((key.clone(), chapter_id.clone()));
+                    chapter_id
+                }
+            };
+            used_keys.push(key.clone());
+            file.key = Some(key);
+        }
+        if keep_ids && (first_listing || known.len() != known_count) {
+            self.network.store_chapter_ids(&id, &known);
+        }
+
+        files.reverse();
+        Ok(files)
+    }
+
+    /// Lists the chapters in a Madokami folder, newest first. With
+    /// `smallest_duplicates`, a file is left out when another one for the
+    /// same chapter is smaller, and a pack is left out when the folder's
+    /// chapter files cover it and are smaller together. IDs are given out
+    /// before that, so they don't depend on the setting.
+    pub fn chapter_list(&self, id: &str) -> SourceResult<Vec<ChapterInfo>> {
+        let files = self.folder_files(id, true)?;
+        if !self.settings.smallest_duplicates {
+            return Ok(files.into_iter().map(|file| file.chapter).collect());
+        }
+        let smallest: Vec<&ListedFile> = files
+            .iter()
+            .filter(|file| !larger_duplicate(file, &files))
+            .collect();
+        Ok(smallest
+            .iter()
+            .filter(|file| !covered_pack(file, &smallest))
+            .map(|file| file.chapter.clone())
+            .collect())
+    }
+
+    /// Finds the reader path for a chapter ID. IDs from before chapters were
+    /// keyed on their number are reader paths already.
+    fn chapter_reader_path(&self, chapter_id: &str) -> SourceResult<String> {
+        let (folder, key) = match chapter_id.split_once("?ch=") {
+            Some((path, _)) if path.starts_with("/reader") => return Ok(String::from(path)),
+            Some((folder, key)) => (folder, key),
+            None if chapter_id.starts_with("/reader") => return Ok(String::from(chapter_id)),
+            None => return Err(SourceError::NotFound(String::from(chapter_id))),
+        };
+        self.folder_files(folder, false)?
+            .into_iter()
+            .find(|file| file.chapter.id == chapter_id || file.key.as_deref() == Some(key))
+            .map(|file| file.reader_path)
+            .ok_or_else(|| SourceError::NotFound(String::from(chapter_id)))
+    }
+
+    /// Finds the file an old reader path ID is for after it has been renamed:
+    /// the one the folder listing still gives that ID, or else the one for the
+    /// same chapter.
+    fn renamed_reader_path(&self, chapter_id: &str) -> SourceResult<String> {
+        let (folder, key) = legacy_chapter_key(chapter_id)
+            .ok_or_else(|| SourceError::NotFound(String::from(chapter_id)))?;
+        let files = self.folder_files(&folder, false)?;
+        files
+            .iter()
+            .find(|file| file.chapter.id == chapter_id)
+            .or_else(|| files.iter().find(|file| file.key.as_deref() == Some(&key)))
+            .map(|file| file.reader_path.clone())
+            .ok_or_else(|| SourceError::NotFound(String::from(chapter_id)))
+    }
+
+    /// The details of a series, from its folder's page.
+    pub fn manga_details(&self, id: &str) -> SourceResult<MangaInfo> {
+        let mut id = canonical_path(id);
+        let mut manga = MangaInfo::default();
+        let mut parent_description = String::new();
+
+        if let Some(parent_path) = get_parent_path(&id) {
+            let parent_url = format!("{}{}", self.base_url(), parent_path);
+            let (parent_html, location) = self.fetch_page(&parent_url)?;
+
+            // A redirect means the series folder was renamed, so move the ID along.
+            let moved_to = canonical_path(&self.strip_base_url(&location));
+            if moved_to != parent_path && moved_to != "/" {
+                id = format!("{}{}", moved_to, &id[parent_path.len()..]);
+            }
+
+            manga.cover = first_attr(
+                &parent_html.select("div.manga-info img[itemprop=\"image\"]"),
+                "src",
+            );
+
+            manga.author = parent_html
+                .select("a[itemprop=\"author\"]")
+                .iter()
+                .map(|node| node.text())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            manga.categories = parent_html
+                .select("div.genres a.tag")
+                .iter()
+                .map(|node| node.text())
+                .collect();
+
+            parent_description = {
+                let og_desc = first_attr(
+                    &parent_html.select("meta[property=\"og:description\"]"),
+                    "content",
+                );
+                let desc = if !og_desc.is_empty() {
+                    og_desc
+                } else {
+                    first_attr(&parent_html.select("meta[name=\"description\"]"), "content")
+                };
+                clean_description(&desc)
+            };
+
+            manga.completed = joined_text(&parent_html.select("span.scanstatus")) == "Yes";
+        }
+
+        let dir_name = id
+            .trim_matches('/')
+            .rsplit('/')
+            .next()
+            .map(url_decode)
+            .unwrap_or_default();
+        manga.description = if !dir_name.is_empty() && !parent_description.is_empty() {
+            format!("{}\n\n{}", dir_name, parent_description)
+        } else if !dir_name.is_empty() {
+            dir_name
+        } else {
+            parent_description
+        };
+        manga.title = extract_manga_title(&id);
+        manga.url = format!("{}{}", self.base_url(), id);
+        manga.id = id;
+        Ok(manga)
+    }
+
+    /// Lists the pages of a chapter. An archive too large to read is shown as
+    /// a page saying so, since Aidoku only shows that the chapter failed.
+    pub fn page_list(&self, chapter_id: &str) -> SourceResult<Vec<PageInfo>> {
+        let reader_path = self.chapter_reader_path(chapter_id)?;
+        let pages = match self.chapter_pages(&reader_path) {
+            // An old ID for a file that has since been renamed.
+            Err(SourceError::NotFound(_)) if chapter_id.starts_with("/reader") => {
+                self.chapter_pages(&self.renamed_reader_path(&reader_path)?)
+            }
+            result => result,
+        };
+        match pages {
+            Err(error @ SourceError::ArchiveTooLarge(_)) => Ok(vec![PageInfo {
+                index: 0,
+                source: PageSource::Text(error.message()),
+            }]),
+            pages => pages,
+        }
+    }
+
+    /// Lists the pages of the chapter at `reader_path`, from the web reader
+    /// or else straight from the archive.
+    fn chapter_pages(&self, reader_path: &str) -> SourceResult<Vec<PageInfo>> {
+        let file = url_decode(reader_path.rsplit('/').next().unwrap_or_default());
+        if let FileKind::Document(format) = file_kind(&file) {
+            return Err(SourceError::UnsupportedFormat(format));
+        }
+        match self.reader_pages(reader_path) {
+            Ok(pages) if !pages.is_empty() => Ok(pages),
+            // The web reader couldn't open the archive, so read it directly.
+            // Other failures, such as Madokami being down, would hit the
+            // download too.
+            Ok(_) | Err(SourceError::Layout(_)) => {
+                let download_url = format!("{}{}", self.base_url(), download_path(reader_path));
+                self.archive_pages(&download_url)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Reads a chapter straight from its archive with range requests, for when
+    /// Madokami's web reader can't open it.
+    fn archive_pages(&self, url: &str) -> SourceResult<Vec<PageInfo>> {
+        let images = archive_pages(&self.network, url, self.settings.skip_credit_pages).map_err(
+            |error| match error {
+                ArchiveError::Status(code) => check_status(code, url)
+                    .err()
+                    .unwrap_or(SourceError::Http(code)),
+                ArchiveError::Zip(error) => SourceError::Archive(error),
+                ArchiveError::Truncated => {
+                    SourceError::Layout("the archive is shorter than its directory says")
+                }
+                ArchiveError::NoPages => SourceError::Layout("the archive has no page images"),
+                ArchiveError::TooLarge(size) => SourceError::ArchiveTooLarge(size),
+            },
+        )?;
+        Ok(images
+            .into_iter()
+            .enumerate()
+            .map(|(index, image)| PageInfo {
+                index: index as i32,
+                source: PageSource::Image(image),
+            })
+            .collect())
+    }
+
+    /// Lists a chapter's pages from Madokami's web reader.
+    fn reader_pages(&self, chapter_id: &str) -> SourceResult<Vec<PageInfo>> {
+        let url = format!("{}{}", self.base_url(), chapter_id);
+        let html = self.fetch_html(&url)?;
+
+        let reader = html.select("div#reader");
+        let path = first_attr(&reader, "data-path");
+        let files = parse_string_array(&first_attr(&reader, "data-files"))
+            .ok_or(SourceError::Layout("the reader page has no file list"))?;
+
+        let pages = page_files(files, self.settings.skip_credit_pages)
+            .into_iter()
+            .enumerate()
+            .map(|(index, filename)| PageInfo {
+                index: index as i32,
+                source: PageSource::Url(format!(
+                    "{}/reader/image?path={}&file={}",
+                    self.base_url(),
+                    url_encode(&path),
+                    url_encode(&filename)
+                )),
+            })
+            .collect();
+        Ok(pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stored_zip, FixtureNetwork};
+
+    const SEARCH: &[u8] = include_bytes!("../fixtures/html/search.html");
+    const RECENT: &[u8] = include_bytes!("../fixtures/html/recent.html");
+    const FOLDER: &[u8] = include_bytes!("../fixtures/html/folder.html");
+    const READER: &[u8] = include_bytes!("../fixtures/html/reader.html");
+    const LOGIN: &[u8] = include_bytes!("../fixtures/html/login.html");
+    const ARCHIVE: &[u8] = include_bytes!("../fixtures/sample.cbz");
+    const OPDS_FOLDER: &[u8] = include_bytes!("../fixtures/opds/folder.xml");
+
+    const NOW: f64 = 1_700_000_000.0;
+    const BERSERK: &str = "/Manga/B/BE/BERS/Berserk";
+    const C001: &str = "/Manga/B/BE/BERS/Berserk/Berserk%20c001%20%5BEvil%20Genius%5D.zip";
+    const C003: &str = "/Manga/B/BE/BERS/Berserk/Berserk%20c003%20part%201.cbz";
+
+    fn url(path: &str) -> String {
+        format!("{}{}", DEFAULT_BASE_URL, path)
+    }
+
+    /// Builds fixtures with chapter IDs kept from an earlier listing.
+    trait KeptIds {
+        fn kept_ids(self, folder: &str, ids: &[(&str, &str)]) -> Self;
+    }
+
+    impl KeptIds for FixtureNetwork {
+        fn kept_ids(self, folder: &str, ids: &[(&str, &str)]) -> Self {
+            let ids: Vec<(String, String)> = ids
+                .iter()
+                .map(|&(key, id)| (String::from(key), String::from(id)))
+                .collect();
+            self.store_chapter_ids(folder, &ids);
+            self
+        }
+    }
+
+    fn site(network: FixtureNetwork) -> Madokami<FixtureNetwork> {
+        Madokami::new(
+            network,
+            Settings {
+                now: NOW,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// A site whose Berserk folder was listed before, when it had no files, so
+    /// its files are given keyed chapter IDs.
+    fn folder_site() -> Madokami<FixtureNetwork> {
+        site(
+            FixtureNetwork::default()
+                .page(&url(BERSERK), FOLDER)
+                .kept_ids(BERSERK, &[]),
+        )
+    }
+
+    fn ids(mangas: &[MangaInfo]) -> Vec<&str> {
+        mangas.iter().map(|manga| manga.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let site = site(FixtureNetwork::default().page(&url("/search?q=berserk"), SEARCH));
+        let (mangas, has_more) = site.manga_list(Some("berserk"), 1).unwrap();
+        assert!(!has_more);
+        assert_eq!(
+            ids(&mangas),
+            [
+                BERSERK,
+                "/Manga/B/BE/BERS/Berserk%20-%20The%20Prototype",
+                "/Manga/_Doujinshi/Berserk%20%26%20Friends",
+            ]
+        );
+        assert_eq!(
+            mangas[2],
+            MangaInfo {
+                id: String::from("/Manga/_Doujinshi/Berserk%20%26%20Friends"),
+                title: String::from("Berserk & Friends"),
+                url: url("/Manga/_Doujinshi/Berserk%20%26%20Friends"),
+                ..Default::default()
+            }
+        );
+
+        // The website has no further pages, so nothing is fetched for them.
+        assert_eq!(
+            site.manga_list(Some("berserk"), 2).unwrap(),
+            (Vec::new(), false)
+        );
+        assert_eq!(site.network().requests().len(), 1);
+    }
+
+    #[test]
+    fn test_recent() {
+        let site = site(FixtureNetwork::default().page(&url("/recent"), RECENT));
+        let (mangas, _) = site.manga_list(None, 1).unwrap();
+        assert_eq!(
+            ids(&mangas),
+            [
+                BERSERK,
+                "/Manga/K/KA/KAIJ/Kaiju%20No.%208",
+                "/Manga/P/PO/POKE/Pok%C3%A9mon%20Adventures",
+            ]
+        );
+        let titles: Vec<&str> = mangas.iter().map(|manga| manga.title.as_str()).collect();
+        assert_eq!(titles, ["Berserk", "Kaiju No. 8", "Pokémon Adventures"]);
+    }
+
+    #[test]
+    fn test_login_wall() {
+        let site = site(
+            FixtureNetwork::default()
+                .page(&url("/recent"), LOGIN)
+                .status(&url("/search?q=berserk"), 401),
+        );
+        assert_eq!(site.manga_list(None, 1), Err(SourceError::Unauthorized));
+        assert_eq!(
+            site.manga_list(Some("berserk"), 1),
+            Err(SourceError::Unauthorized)
+        );
+        assert_eq!(
+            site.chapter_list(BERSERK),
+            Err(SourceError::NotFound(url(BERSERK)))
+        );
+    }
+
+    #[test]
+    fn test_check_login() {
+        let rejected = site(FixtureNetwork::default().page(DEFAULT_BASE_URL, LOGIN));
+        assert_eq!(rejected.check_login(), Err(SourceError::Unauthorized));
+        let accepted = site(FixtureNetwork::default().page(DEFAULT_BASE_URL, RECENT));
+        assert_eq!(accepted.check_login(), Ok(()));
+        let failing = site(FixtureNetwork::default().status(DEFAULT_BASE_URL, 503));
+        assert_eq!(failing.check_login(), Err(SourceError::Server(503)));
+    }
+
+    #[test]
+    fn test_chapter_list() {
+        let chapters = folder_site().chapter_list(BERSERK).unwrap();
+        let ids: Vec<&str> = chapters.iter().map(|chapter| chapter.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "/reader/Manga/B/BE/BERS/Berserk/Berserk%20Artbook.pdf",
+                "/Manga/B/BE/BERS/Berserk?ch=c3-p1",
+                // The newer upload of chapter 2 doesn't take the older one's ID.
+                "/Manga/B/BE/BERS/Berserk?ch=c2-gdankeempire",
+                "/Manga/B/BE/BERS/Berserk?ch=c2",
+                "/Manga/B/BE/BERS/Berserk?ch=c1",
+            ]
+        );
+        assert_eq!(
+            chapters[4],
+            ChapterInfo {
+                id: String::from("/Manga/B/BE/BERS/Berserk?ch=c1"),
+                title: String::from("Berserk c001 [Evil Genius]"),
+                chapter: 1.0,
+                volume: -1.0,
+                date_updated: 1_551_434_400.0,
+                scanlator: String::from("Evil Genius · 20.1 MB"),
+                url: url(&format!("/reader{}", C001)),
+            }
+        );
+        // The date comes from the cell's tooltip when its text isn't one.
+        assert_eq!(chapters[3].date_updated, 1_551_520_800.0);
+        assert_eq!(chapters[2].scanlator, "Danke-Empire · 25.0 MB");
+        assert_eq!(chapters[1].title, "Berserk c003 part 1");
+        // Files without a group still show their size.
+        assert_eq!(chapters[1].scanlator, "18.4 MB");
+        assert_eq!(chapters[1].chapter, 3.01);
+        assert_eq!(chapters[0].title, "Berserk Artbook [PDF]");
+        assert_eq!(chapters[0].chapter, -1.0);
+    }
+
+    #[test]
+    fn test_smallest_duplicates() {
+        let site = Madokami::new(
+            FixtureNetwork::default()
+                .page(&url(BERSERK), FOLDER)
+                .kept_ids(BERSERK, &[]),
+            Settings {
+                smallest_duplicates: true,
+                now: NOW,
+                ..Default::default()
+            },
+        );
+        let chapters = site.chapter_list(BERSERK).unwrap();
+        let ids: Vec<&str> = chapters.iter().map(|chapter| chapter.id.as_str()).collect();
+        // The larger upload of chapter 2 is hidden, and the rest keep their IDs.
+        assert_eq!(
+            ids,
+            [
+                "/reader/Manga/B/BE/BERS/Berserk/Berserk%20Artbook.pdf",
+                "/Manga/B/BE/BERS/Berserk?ch=c3-p1",
+                "/Manga/B/BE/BERS/Berserk?ch=c2",
+                "/Manga/B/BE/BERS/Berserk?ch=c1",
+            ]
+        );
+        assert_eq!(chapters[2].scanlator, "Evil Genius · 19.7 MB");
+        // A hidden chapter that's already in the library can still be opened.
+        assert_eq!(
+            site.chapter_reader_path("/Manga/B/BE/BERS/Berserk?ch=c2-gdankeempire")
+                .unwrap(),
+            "/reader/Manga/B/BE/BERS/Berserk/Berserk%20c002%20%5BDanke-Empire%5D.zip"
+        );
+    }
+
+    #[test]
+    fn test_covered_packs() {
+        let folder = "/Manga/T/TE/TEST/Test";
+        let row = |name: &str, size: &str, day: u32| {
+            format!(
+                "<tr><td><a href=\"/{0}.zip\">{0}.zip</a></td><td>{1}</td><td>2021-01-{2:02} 00:00</td></tr>",
+                name, size, day
+            )
+        };
+        let listing = |rows: &[String]| {
+            let page = format!(
+                "<table id=\"index-table\"><tbody>{}{}</tbody></table>",
+                row("Test v01 (c001-003)", "400 MB", 1),
+                rows.concat()
+            );
+            let site = Madokami::new(
+                FixtureNetwork::default().page(&url(folder), page.as_bytes()),
+                Settings {
+                    smallest_duplicates: true,
+                    now: NOW,
+                    ..Default::default()
+                },
+            );
+            let chapters = site.chapter_list(folder).unwrap();
+            chapters
+                .into_iter()
+                .map(|chapter| chapter.title)
+                .collect::<Vec<_>>()
+        };
+        let c1 = row("Test v01 c001", "20 MB", 2);
+        let c2 = row("Test v01 c002", "20 MB", 3);
+        let c3 = row("Test c003", "20 MB", 4);
+        // The pack is hidden once every chapter it holds has a smaller file.
+        assert_eq!(
+            listing(&[c1.clone(), c2.clone(), c3.clone()]),
+            ["Test c003", "Test v01 c002", "Test v01 c001"]
+        );
+        // Without its last chapter, some of the pack is only in the pack.
+        assert_eq!(
+            listing(&[c1.clone(), c2.clone()]),
+            ["Test v01 c002", "Test v01 c001", "Test v01 (c001-003)"]
+        );
+        // The same goes for its first chapter, or one in between.
+        assert_eq!(
+            listing(&[c2.clone(), c3.clone()]),
+            ["Test c003", "Test v01 c002", "Test v01 (c001-003)"]
+        );
+        assert_eq!(
+            listing(&[c1.clone(), c3.clone()]),
+            ["Test c003", "Test v01 c001", "Test v01 (c001-003)"]
+        );
+        // A part doesn't cover its chapter, since another part may be missing.
+        assert_eq!(
+            listing(&[c1, c2, row("Test c003 part 1", "10 MB", 4)]),
+            [
+                "Test c003 part 1",
+                "Test v01 c002",
+                "Test v01 c001",
+                "Test v01 (c001-003)"
+            ]
+        );
+        // A pack whose name doesn't give its chapters is never hidden.
+        let page = format!(
+            "<table id=\"index-table\"><tbody>{}{}</tbody></table>",
+            row("Test v01", "400 MB", 1),
+            row("Test v01 c005", "20 MB", 2)
+        );
+        let site = Madokami::new(
+            FixtureNetwork::default().page(&url(folder), page.as_bytes()),
+            Settings {
+                smallest_duplicates: true,
+                now: NOW,
+                ..Default::default()
+            },
+        );
+        let titles: Vec<String> = site
+            .chapter_list(folder)
+            .unwrap()
+            .into_iter()
+            .map(|chapter| chapter.title)
+            .collect();
+        assert_eq!(titles, ["Test v01 c005", "Test v01"]);
+    }
+
+    #[test]
+    fn test_escaped_text() {
+        // Text is decoded by the HTML parser, so escaped entities stay as
+        // they are rather than being decoded twice.
+        let folder = "/Manga/T/TO/TOMJ/Tom%20%26%20Jerry";
+        let site = site(FixtureNetwork::default().page(
+            &url(folder),
+            b"<table id=\"index-table\"><tbody><tr>\
+              <td><a href=\"/Manga/T/TO/TOMJ/Tom%20%26%20Jerry/a.zip\">Tom &amp; Jerry &amp;lt;3 c001.zip</a></td>\
+              <td>1 MB</td><td>2021-01-01 00:00</td></tr></tbody></table>",
+        ));
+        let chapters = site.chapter_list(folder).unwrap();
+        assert_eq!(chapters[0].title, "Tom & Jerry &lt;3 c001");
+    }
+
+    #[test]
+    fn test_undated_files() {
+        let folder = "/Manga/T/TE/TEST/Test";
+        let listing = |now: f64| {
+            Madokami::new(
+                FixtureNetwork::default().page(
+                    &url(folder),
+                    b"<table id=\"index-table\"><tbody>\
+                      <tr><td><a href=\"/a.zip\">Test c001 [A].zip</a></td><td></td><td>soon</td></tr>\
+                      <tr><td><a href=\"/b.zip\">Test c001 [B].zip</a></td><td></td><td>2021-01-01 00:00</td></tr>\
+                      </tbody></table>",
+                    )
+                    .kept_ids(folder, &[]),
+                Settings {
+                    now,
+                    ..Default::default()
+                },
+            )
+            .chapter_list(folder)
+            .unwrap()
+        };
+        let chapters = listing(NOW);
+        // A date that can't be read is left unknown, so it doesn't change
+        // from one refresh to the next or take the dated file's key.
+        assert_eq!(chapters[1].date_updated, UNKNOWN_DATE);
+        assert_eq!(chapters[1].id, "/Manga/T/TE/TEST/Test?ch=c1-ga");
+        assert_eq!(chapters[0].id, "/Manga/T/TE/TEST/Test?ch=c1");
+        assert_eq!(listing(NOW + 3600.0), chapters);
+    }
+
+    #[test]
+    fn test_reordered_columns() {
+        let folder = "<table id=\"index-table\"><thead><tr>\
+            <th>Last Modified</th><th></th><th>Name</th><th>Size</th></tr></thead><tbody>\
+            <tr><td>2019-03-01 10:00</td>\
+            <td><a href=\"/reader/Manga/B/BE/BERS/Berserk/Berserk%20c001.zip\">Read</a></td>\
+            <td><a href=\"/Manga/B/BE/BERS/Berserk/Berserk%20c001.zip\">Berserk c001.zip</a></td>\
+            <td>20 MB</td></tr>\
+            <tr><td>2019-03-02 10:00</td><td></td>\
+            <td><a href=\"/Manga/B/BE/BERS/Berserk/Berserk%20c002.zip\">Berserk c002.zip</a></td>\
+            <td>19 MB</td></tr></tbody></table>";
+        let chapters = site(FixtureNetwork::default().page(&url(BERSERK), folder.as_bytes()))
+            .chapter_list(BERSERK)
+            .unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].title, "Berserk c001");
+        assert_eq!(chapters[1].date_updated, 1_551_434_400.0);
+        assert_eq!(
+            chapters[1].url,
+            url("/reader/Manga/B/BE/BERS/Berserk/Berserk%20c001.zip")
+        );
+        // Without a reader link, the download link is opened in the reader.
+        assert_eq!(
+            chapters[0].url,
+            url("/reader/Manga/B/BE/BERS/Berserk/Berserk%20c002.zip")
+        );
+
+        let recent = "<table class=\"mobile-files-table\"><thead><tr>\
+            <th>Uploaded</th><th>Series</th></tr></thead><tbody>\
+            <tr><td><a href=\"/Manga/B/BE/BERS/Berserk/Berserk%20c002.zip\">today</a></td>\
+            <td><a href=\"/Manga/B/BE/BERS/Berserk\">Berserk</a></td></tr></tbody></table>";
+        let (mangas, _) = site(FixtureNetwork::default().page(&url("/recent"), recent.as_bytes()))
+            .manga_list(None, 1)
+            .unwrap();
+        assert_eq!(ids(&mangas), [BERSERK]);
+    }
+
+    #[test]
+    fn test_missing_columns() {
+        let folder = "<table id=\"index-table\"><thead><tr><th>Name</th><th>Size</th></tr></thead>\
+            <tbody><tr><td><a href=\"/a.zip\">a.zip</a></td><td>1 MB</td></tr></tbody></table>";
+        let site = site(
+            FixtureNetwork::default()
+                .page(&url(BERSERK), folder.as_bytes())
+                .page(
+                    &url("/search?q=berserk"),
+                    b"<div class=\"container\"><table><thead><tr><th>Path</th></tr></thead></table></div>",
+                ),
+        );
+        assert_eq!(
+            site.chapter_list(BERSERK),
+            Err(SourceError::Layout("the folder table has no Date column"))
+        );
+        assert_eq!(
+            site.manga_list(Some("berserk"), 1),
+            Err(SourceError::Layout(
+                "the search results table has no Name column"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_opds_chapter_list() {
+        let site = Madokami::new(
+            FixtureNetwork::default()
+                .page(&url("/opds/Manga/B/BE/BERS/Berserk"), OPDS_FOLDER)
+                .page(
+                    &url("/opds/Manga/B/BE/BERS/Berserk?page=2"),
+                    b"<feed xmlns=\"http://www.w3.org/2005/Atom\"><title>Berserk</title></feed>",
+                )
+                .kept_ids(BERSERK, &[]),
+            Settings {
+                backend: Backend::Opds,
+                now: NOW,
+                ..Default::default()
+            },
+        );
+        let chapters = site.chapter_list(BERSERK).unwrap();
+        let ids: Vec<&str> = chapters.iter().map(|chapter| chapter.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "/Manga/B/BE/BERS/Berserk?ch=v2",
+                "/Manga/B/BE/BERS/Berserk?ch=v1"
+            ]
+        );
+        assert_eq!(chapters[1].title, "Berserk v01 (2003) [Digital]");
+        assert_eq!(chapters[1].volume, 1.0);
+        assert_eq!(chapters[1].date_updated, 1_620_131_400.0);
+        assert_eq!(chapters[1].scanlator, "394 MB");
+        assert_eq!(
+            chapters[1].url,
+            url("/reader/Manga/B/BE/BERS/Berserk/Berserk%20v01%20%282003%29%20%5BDigital%5D.cbz")
+        );
+    }
+
+    #[test]
+    fn test_manga_details() {
+        let manga = folder_site().manga_details(BERSERK).unwrap();
+        assert_eq!(
+            manga,
+            MangaInfo {
+                id: String::from(BERSERK),
+                title: String::from("Berserk"),
+                author: String::from("Miura Kentarou, Mori Kōji"),
+                cover: String::from("https://cdn.mangaupdates.com/image/i270127.jpg"),
+                description: String::from(
+                    "Berserk\n\nGuts, a former mercenary now known as the \"Black Swordsman\", \
+                     is out for revenge."
+                ),
+                categories: Vec::from([
+                    String::from("Action"),
+                    String::from("Dark Fantasy"),
+                    String::from("Mature"),
+                ]),
+                url: url(BERSERK),
+                completed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_renamed_folder() {
+        let redirected = site(
+            FixtureNetwork::default()
+                .redirect(&url("/Manga/B/BE/BERS/Berserk%20%28Old%29"), BERSERK)
+                .page(&url(BERSERK), FOLDER),
+        );
+        let manga = redirected
+            .manga_details("/Manga/B/BE/BERS/Berserk (Old)/")
+            .unwrap();
+        assert_eq!(manga.id, BERSERK);
+        assert_eq!(manga.url, url(BERSERK));
+
+        // Aidoku follows redirects without showing them, so the renamed
+        // folder's page comes back at the old URL.
+        let followed = site(
+            FixtureNetwork::default().page(&url("/Manga/B/BE/BERS/Berserk%20%28Old%29"), FOLDER),
+        );
+        let manga = followed
+            .manga_details("/Manga/B/BE/BERS/Berserk (Old)")
+            .unwrap();
+        assert_eq!(manga.id, BERSERK);
+    }
+
+    #[test]
+    fn test_page_list() {
+        let reader = format!("/reader{}", C001);
+        let network = || {
+            FixtureNetwork::default()
+                .page(&url(BERSERK), FOLDER)
+                .page(&url(&reader), READER)
+        };
+        let pages = site(network())
+            .page_list("/Manga/B/BE/BERS/Berserk?ch=c1")
+            .unwrap();
+        let image = |file: &str| {
+            PageSource::Url(url(&format!(
+                "/reader/image?path=%2FManga%2FB%2FBE%2FBERS%2FBerserk%2FBerserk%20c001%20%5BEvil%20Genius%5D.zip&file={}",
+                file
+            )))
+        };
+        assert_eq!(
+            pages,
+            [
+                PageInfo {
+                    index: 0,
+                    source: image("Berserk_c001_p001.jpg"),
+                },
+                PageInfo {
+                    index: 1,
+                    source: image("Berserk_c001_p002.jpg"),
+                },
+                PageInfo {
+                    index: 2,
+                    source: image("Berserk_c001_p010.jpg"),
+                },
+                PageInfo {
+                    index: 3,
+                    source: image("credits.png"),
+                },
+            ]
+        );
+
+        let site = Madokami::new(
+            network(),
+            Settings {
+                skip_credit_pages: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(site.page_list(&reader).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_archive_pages() {
+        // The web reader couldn't open this archive, so its pages are read
+        // straight from the download.
+        let site = site(
+            FixtureNetwork::default()
+                .page(
+                    &url(&format!("/reader{}", C003)),
+                    b"<div id=\"reader\" data-path=\"\" data-files=\"[]\"></div>",
+                )
+                .page(&url(C003), ARCHIVE),
+        );
+        let pages = site.page_list(&format!("/reader{}", C003)).unwrap();
+        assert_eq!(
+            pages,
+            [
+                PageInfo {
+                    index: 0,
+                    source: PageSource::Image(vec![0x42; 4096]),
+                },
+                PageInfo {
+                    index: 1,
+                    source: PageSource::Image(Vec::from(&b"\x89PNG stored page"[..])),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_archive_too_large() {
+        let site = site(
+            FixtureNetwork::default()
+                .page(
+                    &url(&format!("/reader{}", C003)),
+                    b"<div id=\"reader\" data-path=\"\" data-files=\"[]\"></div>",
+                )
+                .page(
+                    &url(C003),
+                    &stored_zip(&[
+                        ("01.jpg", vec![1; 10], 40 << 20),
+                        ("02.jpg", vec![2; 10], 40 << 20),
+                    ]),
+                ),
+        );
+        let pages = site.page_list(&format!("/reader{}", C003)).unwrap();
+        assert_eq!(
+            pages,
+            [PageInfo {
+                index: 0,
+                source: PageSource::Text(String::from(
+                    "This chapter's archive holds 80.0 MB of pages, and only archives of up to \
+                     64.0 MB can be read without Madokami's web reader. Download it from \
+                     Madokami instead."
+                )),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reader_failure() {
+        // Madokami being down isn't a reason to download the whole archive.
+        let site = site(
+            FixtureNetwork::default()
+                .status(&url(&format!("/reader{}", C003)), 503)
+                .page(&url(C003), ARCHIVE),
+        );
+        assert_eq!(
+            site.page_list(&format!("/reader{}", C003)),
+            Err(SourceError::Server(503))
+        );
+        assert!(!site.network.requests().contains(&url(C003)));
+    }
+
+    #[test]
+    fn test_part_titles() {
+        let site = site(FixtureNetwork::default());
+        let title = |filename: &str| {
+            site.listed_file("Berserk", filename, String::new(), None, None)
+                .unwrap()
+                .chapter
+                .title
+        };
+        assert_eq!(title("Berserk c012-1.zip"), "Berserk c012-1 (Part 1)");
+        assert_eq!(title("Berserk c012p2.zip"), "Berserk c012p2 (Part 2)");
+        // Parts that are already written out aren't labelled again.
+        assert_eq!(title("Berserk c012 part 1.zip"), "Berserk c012 part 1");
+        assert_eq!(title("Berserk c012 (Part 2).zip"), "Berserk c012 (Part 2)");
+    }
+
+    #[test]
+    fn test_unreadable_chapters() {
+        let site = folder_site();
+        assert_eq!(
+            site.page_list("/reader/Manga/B/BE/BERS/Berserk/Berserk%20Artbook.pdf"),
+            Err(SourceError::UnsupportedFormat("PDF"))
+        );
+        assert_eq!(
+            site.page_list("/Manga/B/BE/BERS/Berserk?ch=c9"),
+            Err(SourceError::NotFound(String::from(
+                "/Manga/B/BE/BERS/Berserk?ch=c9"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_legacy_chapter_ids() {
+        // The first listing since IDs were keyed keeps the reader path IDs
+        // that Aidoku's read history is stored under.
+        let site = site(FixtureNetwork::default().page(&url(BERSERK), FOLDER));
+        let chapters = site.chapter_list(BERSERK).unwrap();
+        assert_eq!(chapters[4].id, format!("/reader{}", C001));
+        assert_eq!(chapters[1].id, format!("/reader{}", C003));
+
+        // Files uploaded since are keyed, while the old ones keep their IDs.
+        let site = site_with_ids(&[
+            ("c1", "/reader/Manga/B/BE/BERS/Berserk/Berserk%20c001.zip"),
+            ("c2", "/Manga/B/BE/BERS/Berserk?ch=c2"),
+        ]);
+        let chapters = site.chapter_list(BERSERK).unwrap();
+        let ids: Vec<&str> = chapters.iter().map(|chapter| chapter.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "/reader/Manga/B/BE/BERS/Berserk/Berserk%20Artbook.pdf",
+                "/Manga/B/BE/BERS/Berserk?ch=c3-p1",
+                "/Manga/B/BE/BERS/Berserk?ch=c2-gdankeempire",
+                "/Manga/B/BE/BERS/Berserk?ch=c2",
+                // Renamed since it was first listed, and still under its old ID.
+                "/reader/Manga/B/BE/BERS/Berserk/Berserk%20c001.zip",
+            ]
+        );
+        assert_eq!(
+            site.network().chapter_ids(BERSERK).unwrap().len(),
+            4,
+            "the new files' IDs are kept for the next listing"
+        );
+    }
+
+    fn site_with_ids(ids: &[(&str, &str)]) -> Madokami<FixtureNetwork> {
+        site(
+            FixtureNetwork::default()
+                .page(&url(BERSERK), FOLDER)
+                .kept_ids(BERSERK, ids),
+        )
+    }
+
+    #[test]
+    fn test_renamed_chapter() {
+        // A chapter ID from before IDs were keyed, for a file renamed since.
+        let site = site(
+            FixtureNetwork::default()
+                .page(&url(BERSERK), FOLDER)
+                .page(&url(&format!("/reader{}", C001)), READER),
+        );
+        let pages = site
+            .page_list("/reader/Manga/B/BE/BERS/Berserk/Berserk%20c001.zip")
+            .unwrap();
+        assert_eq!(pages.len(), 4);
+    }
+
+    #[test]
+    fn test_handle_url() {
+        let folder = folder_site();
+        let target = folder
+            .handle_url(&url(
+                "/reader/Manga/B/BE/BERS/Berserk/Berserk%20c002%20%5BDanke-Empire%5D.zip?page=3",
+            ))
+            .unwrap();
+        assert_eq!(target.manga.id, BERSERK);
+        assert_eq!(
+            target.chapter_id.as_deref(),
+            Some("/Manga/B/BE/BERS/Berserk?ch=c2-gdankeempire")
+        );
+
+        // Following a link doesn't keep IDs for the folder; only listing its
+        // chapters does.
+        let unlisted = site(FixtureNetwork::default().page(&url(BERSERK), FOLDER));
+        let target = unlisted
+            .handle_url(&url(
+                "/reader/Manga/B/BE/BERS/Berserk/Berserk%20c002%20%5BDanke-Empire%5D.zip",
+            ))
+            .unwrap();
+        assert_eq!(
+            target.chapter_id.as_deref(),
+            Some("/reader/Manga/B/BE/BERS/Berserk/Berserk%20c002%20%5BDanke-Empire%5D.zip")
+        );
+        assert_eq!(unlisted.network().chapter_ids(BERSERK), None);
+
+        let target = folder
+            .handle_url(&url("/Manga/B/BE/BERS/Berserk/%21Extras/"))
+            .unwrap();
+        assert_eq!(target.manga.id, BERSERK);
+        assert_eq!(target.chapter_id, None);
+
+        let search = site(FixtureNetwork::default().page(&url("/search?q=Berserk"), SEARCH));
+        let target = search.handle_url(&url("/search?q=Berserk")).unwrap();
+        assert_eq!(target.manga.id, BERSERK);
+        assert_eq!(target.manga.title, "Berserk");
+
+        // A link to the home page isn't a layout change, just not a series.
+        assert_eq!(
+            search.handle_url(&url("/")),
+            Err(SourceError::NotFound(String::from("/")))
+        );
+        // Nor is the same path on another host.
+        assert_eq!(
+            search.handle_url("https://evil.example/Manga/B"),
+            Err(SourceError::NotFound(String::from(
+                "https://evil.example/Manga/B"
+            )))
+        );
+        assert_eq!(
+            search.strip_base_url("https://MANGA.madokami.al:443/Manga/B"),
+            "/Manga/B"
+        );
+        let mirror = Madokami::new(
+            FixtureNetwork::default(),
+            Settings {
+                base_url: configured_base_url("https://mirror.example/madokami/"),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            mirror.strip_base_url("https://mirror.example/madokami/Manga/B"),
+            "/Manga/B"
+        );
+        assert_eq!(
+            mirror
+                .handle_url("https://mirror.example/madokami/Manga/B/BE/BERS/Berserk")
+                .unwrap()
+                .manga
+                .id,
+            BERSERK
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Keeping the chapter IDs given out for each folder's files, so a file keeps
+//! its ID across refreshes and renames.
+
+use alloc::{format, string::String, vec::Vec};
+
+/// How many folders' chapter IDs are kept. Listing a folder's chapters makes
+/// it the most recent, so only folders that haven't been listed in a long
+/// time are dropped, and their files go back to their reader paths as IDs.
+pub const MAX_KEPT_FOLDERS: usize = 500;
+
+/// The setting listing the folders whose chapter IDs are kept, oldest first.
+const KEPT_FOLDERS_KEY: &str = "chapterIdFolders";
+
+/// Settings that outlive the source, such as Aidoku's defaults, used to keep
+/// chapter IDs.
+pub trait ChapterIdStore {
+    /// A setting's value, if it was ever set.
+    fn setting(&self, key: &str) -> Option<String>;
+
+    /// Sets a setting's value.
+    fn set_setting(&self, key: &str, value: &str);
+
+    /// The chapter IDs given out for a folder's files so far, as pairs of the
+    /// key each was given for and the ID. `None` when the folder's chapters
+    /// haven't been listed since IDs started being kept, or were dropped.
+    fn chapter_ids(&self, folder: &str) -> Option<Vec<(String, String)>> {
+        if !kept_folders(self).iter().any(|kept| kept == folder) {
+            return None;
+        }
+        let kept = self.setting(&chapter_ids_key(folder))?;
+        Some(
+            kept.lines()
+                .filter_map(|line| line.split_once('\t'))
+                .map(|(key, id)| (String::from(key), String::from(id)))
+                .collect(),
+        )
+    }
+
+    /// Keeps the chapter IDs given out for a folder's files, dropping the
+    /// ones of the folders listed least recently past `MAX_KEPT_FOLDERS`.
+    fn store_chapter_ids(&self, folder: &str, ids: &[(String, String)]) {
+        let mut folders = kept_folders(self);
+        folders.retain(|kept| kept != folder);
+        folders.push(String::from(folder));
+        let dropped = folders.len().saturating_sub(MAX_KEPT_FOLDERS);
+        for old in folders.drain(..dropped) {
+            self.set_setting(&chapter_ids_key(&old), "");
+        }
+        let kept = ids
+            .iter()
+            .map(|(key, id)| format!("{}\t{}", key, id))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.set_setting(&chapter_ids_key(folder), &kept);
+        self.set_setting(KEPT_FOLDERS_KEY, &folders.join("\n"));
+    }
+}
+
+/// The folders whose chapter IDs are kept, oldest first.
+fn kept_folders<S: ChapterIdStore + ?Sized>(store: &S) -> Vec<String> {
+    store
+        .setting(KEPT_FOLDERS_KEY)
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+/// Where the chapter IDs of a folder's files are kept, one `key<TAB>id` line
+/// each.
+fn chapter_ids_key(folder: &str) -> String {
+    format!("chapterIds:{}", folder)
+}
+
+#[cfg(test)]
+impl ChapterIdStore for crate::FixtureNetwork {
+    fn setting(&self, key: &str) -> Option<String> {
+        crate::FixtureNetwork::setting(self, key)
+    }
+
+    fn set_setting(&self, key: &str, value: &str) {
+        crate::FixtureNetwork::set_setting(self, key, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixtureNetwork;
+
+    #[test]
+    fn test_kept_folders() {
+        let store = FixtureNetwork::default();
+        let ids = [(String::from("c1"), String::from("/Manga/A?ch=c1"))];
+        assert_eq!(store.chapter_ids("/Manga/A"), None);
+        store.store_chapter_ids("/Manga/A", &ids);
+        store.store_chapter_ids("/Manga/B", &[]);
+        assert_eq!(store.chapter_ids("/Manga/A").as_deref(), Some(&ids[..]));
+        assert_eq!(store.chapter_ids("/Manga/B"), Some(Vec::new()));
+
+        // Listing A again keeps it past the folders listed after B.
+        store.store_chapter_ids("/Manga/A", &ids);
+        for index in 0..MAX_KEPT_FOLDERS - 1 {
+            store.store_chapter_ids(&format!("/Manga/{}", index), &[]);
+        }
+        assert_eq!(store.chapter_ids("/Manga/B"), None);
+        assert_eq!(store.setting("chapterIds:/Manga/B").as_deref(), Some(""));
+        assert_eq!(store.chapter_ids("/Manga/A").as_deref(), Some(&ids[..]));
+    }
+}
@@ -0,0 +1,185 @@
+#![no_std]
+extern crate alloc;
+
+use aidoku::{
+    error::Result,
+    prelude::*,
+    std::{current_date, defaults::defaults_get, net::Request, String, Vec},
+    Chapter, Filter, FilterType, Manga, MangaPageResult, MangaStatus, MangaViewer, Page,
+};
+use alloc::vec;
+use base64::{engine::general_purpose, Engine};
+use opds_parser::{
+    Catalog, ChapterInfo, MangaInfo, PageInfo, PageSource, Settings, SourceError, SourceResult,
+    IMAGE_ACCEPT,
+};
+
+mod error;
+mod net;
+use error::*;
+use net::*;
+
+/// The ID of the placeholder entry shown until a catalog is set up.
+const SETUP_GUIDE_ID: &str = "aidoku/setup";
+
+/// Whether scanlator credit and recruitment pages are left out of books read
+/// from their archive.
+fn skip_credit_pages() -> bool {
+    defaults_get("skipCreditPages")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Runs `handler` against the live catalog with the current settings.
+fn with_catalog<T, F>(handler: F) -> SourceResult<T>
+where
+    F: FnOnce(&Catalog<LiveNetwork>) -> SourceResult<T>,
+{
+    let settings = Settings {
+        catalog_url: setting("catalogUrl"),
+        browse_url: setting("browseUrl"),
+        skip_credit_pages: skip_credit_pages(),
+        now: current_date(),
+    };
+    handler(&Catalog::new(LiveNetwork, settings))
+}
+
+/// A placeholder entry explaining how to set up the catalog, shown in place of
+/// an empty list when there is none or the server wants credentials.
+fn setup_guide(error: &SourceError) -> Manga {
+    let description = match error {
+        SourceError::Unauthorized => {
+            "The OPDS server rejected the login. Check the username and password in this \
+             source's settings, then pull to refresh."
+        }
+        _ => {
+            "Enter the address of your OPDS server's catalog in this source's settings, then \
+             pull to refresh."
+        }
+    };
+    Manga {
+        id: String::from(SETUP_GUIDE_ID),
+        title: String::from("Set up your OPDS server"),
+        description: String::from(description),
+        status: MangaStatus::Unknown,
+        viewer: MangaViewer::Rtl,
+        ..Default::default()
+    }
+}
+
+fn manga(info: MangaInfo) -> Manga {
+    Manga {
+        id: info.id,
+        title: info.title,
+        author: info.author,
+        cover: info.cover,
+        description: info.description,
+        categories: info.categories,
+        url: info.url,
+        status: if info.completed {
+            MangaStatus::Completed
+        } else {
+            MangaStatus::Unknown
+        },
+        viewer: MangaViewer::Rtl,
+        ..Default::default()
+    }
+}
+
+fn chapter(info: ChapterInfo) -> Chapter {
+    Chapter {
+        id: info.id,
+        title: info.title,
+        chapter: info.chapter,
+        volume: info.volume,
+        date_updated: info.date_updated,
+        scanlator: info.scanlator,
+        url: info.url,
+        ..Default::default()
+    }
+}
+
+fn page(info: PageInfo) -> Page {
+    match info.source {
+        PageSource::Url(url) => Page {
+            index: info.index,
+            url,
+            ..Default::default()
+        },
+        PageSource::Image(image) => Page {
+            index: info.index,
+            base64: general_purpose::STANDARD.encode(image),
+            ..Default::default()
+        },
+        PageSource::Text(text) => Page {
+            index: info.index,
+            text,
+            ..Default::default()
+        },
+    }
+}
+
+#[initialize]
+fn initialize() {
+    source_common::live::set_rate_limit();
+}
+
+#[get_manga_list]
+fn get_manga_list(filters: Vec<Filter>, page: i32) -> Result<MangaPageResult> {
+    let query = filters
+        .into_iter()
+        .find(|f| matches!(f.kind, FilterType::Title))
+        .and_then(|f| f.value.as_string().ok())
+        .map(|s| s.read());
+
+    match with_catalog(|catalog| catalog.manga_list(query.as_deref(), page)) {
+        Ok((mangas, has_more)) => Ok(MangaPageResult {
+            manga: mangas.into_iter().map(manga).collect(),
+            has_more,
+        }),
+        Err(error @ (SourceError::NotConfigured | SourceError::Unauthorized)) => {
+            Ok(MangaPageResult {
+                manga: vec![setup_guide(&error)],
+                has_more: false,
+            })
+        }
+        Err(error) => Err(aidoku_error(error)),
+    }
+}
+
+#[get_manga_details]
+fn get_manga_details(id: String) -> Result<Manga> {
+    if id == SETUP_GUIDE_ID {
+        return Ok(setup_guide(&SourceError::NotConfigured));
+    }
+    let details = with_catalog(|catalog| catalog.manga_details(&id)).map_err(aidoku_error)?;
+    Ok(manga(details))
+}
+
+#[get_chapter_list]
+fn get_chapter_list(id: String) -> Result<Vec<Chapter>> {
+    if id == SETUP_GUIDE_ID {
+        return Ok(Vec::new());
+    }
+    let chapters = with_catalog(|catalog| catalog.chapter_list(&id)).map_err(aidoku_error)?;
+    Ok(chapters.into_iter().map(chapter).collect())
+}
+
+#[get_page_list]
+fn get_page_list(manga_id: String, chapter_id: String) -> Result<Vec<Page>> {
+    let pages =
+        with_catalog(|catalog| catalog.page_list(&manga_id, &chapter_id)).map_err(aidoku_error)?;
+    Ok(pages.into_iter().map(page).collect())
+}
+
+#[modify_image_request]
+fn modify_image_request(request: Request) -> Request {
+    with_headers(request, IMAGE_ACCEPT)
+}
+
+#[handle_notification]
+fn handle_notification(notification: String) {
+    if notification == "server" {
+        source_common::cache::clear();
+    }
+}
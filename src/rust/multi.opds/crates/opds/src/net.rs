@@ -0,0 +1,57 @@
+use aidoku::std::{defaults::defaults_get, net::Request, String, Vec};
+use opds_parser::{same_host, Network, Response, FEED_ACCEPT};
+use source_common::{
+    cache,
+    live::{self, basic_auth},
+};
+
+const USER_AGENT: &str = "Aidoku";
+
+/// Reads a text setting, trimmed.
+pub fn setting(key: &str) -> String {
+    let value = defaults_get(key)
+        .and_then(|v| v.as_string())
+        .map(|s| s.read())
+        .unwrap_or_default();
+    String::from(value.trim())
+}
+
+/// Adds HTTP Basic authentication to requests to the catalog's host when
+/// credentials are set. Covers linked from other sites must never see them.
+fn add_auth_to_request(request: Request) -> Request {
+    let on_catalog = same_host(&request.url().read(), &setting("catalogUrl"));
+    match basic_auth(&setting("username"), &setting("password")) {
+        Some(auth) if on_catalog => request.header("Authorization", &auth),
+        _ => request,
+    }
+}
+
+/// Applies the headers every request carries: credentials, user agent and
+/// `Accept`.
+pub fn with_headers(request: Request, accept: &str) -> Request {
+    add_auth_to_request(request)
+        .header("User-Agent", USER_AGENT)
+        .header("Accept", accept)
+}
+
+/// The catalog's server over Aidoku's networking, with the stored credentials,
+/// retries and the feed cache.
+pub struct LiveNetwork;
+
+impl Network for LiveNetwork {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Response {
+        let (accept, extra): (Vec<_>, Vec<_>) = headers
+            .iter()
+            .partition(|(name, _)| name.eq_ignore_ascii_case("Accept"));
+        let accept = accept.first().map_or(FEED_ACCEPT, |(_, value)| value);
+        live::get(url, &extra, |request| with_headers(request, accept))
+    }
+
+    fn cached(&self, url: &str) -> Option<(Vec<u8>, String)> {
+        cache::get(url)
+    }
+
+    fn store(&self, url: &str, location: &str, body: &[u8]) {
+        cache::insert(url, location, body)
+    }
+}
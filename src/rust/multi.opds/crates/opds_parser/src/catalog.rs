@@ -0,0 +1,721 @@
+//! Reading an OPDS catalog as series and chapters: browse and search feeds
+//! list series, a series' feed lists its books, and books are read with page
+//! streaming or from their archive.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::{
+    archive_pages, clean_filename, extension_len, file_kind, fill_search_template, parse_date,
+    parse_feed, parse_filename, parse_search_description, resolve_url, url_decode, ArchiveError,
+    ChapterInfo, FileKind, MangaInfo, Network, OpdsEntry, OpdsFeed, OpdsLink, PageInfo, PageSource,
+    SourceError, SourceResult, ZipError, FEED_ACCEPT, UNKNOWN_DATE,
+};
+
+pub const SEARCH_ACCEPT: &str = "application/opensearchdescription+xml,application/xml;q=0.9";
+/// How many pages of a series' feed are followed when listing its books.
+const MAX_FEED_PAGES: usize = 50;
+/// The fragment that marks a book listed on its own, such as on Calibre-web,
+/// whose ID is its download link's path rather than a feed.
+const BOOK_FRAGMENT: &str = "#book";
+
+/// What the source settings hold.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// The root feed of the catalog.
+    pub catalog_url: String,
+    /// The feed shown when browsing, or empty for the catalog.
+    pub browse_url: String,
+    /// Whether scanlator credit and recruitment pages are left out of books
+    /// read from their archive.
+    pub skip_credit_pages: bool,
+    /// The current time, which relative dates are read against.
+    pub now: f64,
+}
+
+/// An OPDS catalog, reached through `N`.
+pub struct Catalog<N: Network> {
+    network: N,
+    settings: Settings,
+}
+
+/// Tells apart missing credentials, missing paths and server failures by
+/// status code.
+pub fn check_status(code: i32, url: &str) -> SourceResult<()> {
+    match code {
+        200..=299 => Ok(()),
+        401 | 403 => Err(SourceError::Unauthorized),
+        404 | 410 => Err(SourceError::NotFound(String::from(url))),
+        500..=599 => Err(SourceError::Server(code)),
+        _ => Err(SourceError::Http(code)),
+    }
+}
+
+/// The download path of a book listed on its own, or `None` for a series. IDs
+/// saved before books were keyed on their path alone carry their title and
+/// cover in the fragment, and are still read as books.
+fn book_path(id: &str) -> Option<&str> {
+    id.split_once('#').map(|(path, _)| path)
+}
+
+/// The name of a book from its download link, when the link ends in a file
+/// name. Links such as Calibre-web's `/opds/download/7/cbz/` don't give one.
+fn book_title(path: &str) -> String {
+    let name = url_decode(path.rsplit('/').next().unwrap_or_default());
+    if extension_len(&name).is_some() {
+        clean_filename(&name)
+    } else {
+        String::new()
+    }
+}
+
+/// The format of a book Aidoku can't show, going by its media type or else the
+/// extension of its download link. Calibre-web's links name the format as
+/// their last part instead, as in `/opds/download/12/epub/`.
+pub fn document_format(url: &str, kind: &str) -> Option<&'static str> {
+    match kind.split(';').next().unwrap_or_default().trim() {
+        "application/pdf" => return Some("PDF"),
+        "application/epub+zip" => return Some("EPUB"),
+        "application/x-mobipocket-ebook" => return Some("MOBI"),
+        _ => {}
+    }
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name = url_decode(
+        path.trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default(),
+    );
+    let name = if name.contains('.') {
+        name
+    } else {
+        format!(".{}", name)
+    };
+    match file_kind(&name) {
+        FileKind::Document(format) => Some(format),
+        FileKind::Pages | FileKind::Text => None,
+    }
+}
+
+/// The page stream of an entry, with its template made absolute.
+fn entry_stream(feed_url: &str, entry: &OpdsEntry) -> Option<(String, u32)> {
+    let link = entry.page_stream()?;
+    Some((resolve_url(feed_url, &link.href), link.count?))
+}
+
+impl<N: Network> Catalog<N> {
+    pub fn new(network: N, settings: Settings) -> Self {
+        Catalog { network, settings }
+    }
+
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+
+    /// The root feed of the catalog, once one is set up.
+    fn catalog_url(&self) -> SourceResult<&str> {
+        let url = self.settings.catalog_url.trim();
+        if url.starts_with("https://") || url.starts_with("http://") {
+            Ok(url)
+        } else {
+            Err(SourceError::NotConfigured)
+        }
+    }
+
+    /// The full URL for an ID or link, which is relative to the catalog's host
+    /// unless it is on another one.
+    fn absolute_url(&self, path: &str) -> SourceResult<String> {
+        Ok(resolve_url(self.catalog_url()?, path))
+    }
+
+    /// The ID for a URL: its path on the catalog's host, or the whole URL for
+    /// links to other hosts.
+    fn relative_path(&self, url: &str) -> SourceResult<String> {
+        let root = resolve_url(self.catalog_url()?, "/");
+        let origin = root.trim_end_matches('/');
+        match url.strip_prefix(origin) {
+            Some(path) if path.starts_with('/') => Ok(String::from(path)),
+            Some("") => Ok(String::from("/")),
+            _ => Ok(String::from(url)),
+        }
+    }
+
+    /// A link's target as an ID, relative to the catalog's host.
+    fn link_path(&self, feed_url: &str, link: &OpdsLink) -> SourceResult<String> {
+        self.relative_path(&resolve_url(feed_url, &link.href))
+    }
+
+    /// Fetches the body at `url`.
+    fn fetch_data(&self, url: &str, accept: &str) -> SourceResult<Vec<u8>> {
+        let response = self.network.get(url, &[("Accept", accept)]);
+        check_status(response.status, url)?;
+        Ok(response.body)
+    }
+
+    /// Fetches a feed and parses it. Recently fetched feeds are served from
+    /// the cache, so following a series' pages to open one of its books
+    /// doesn't fetch them all again.
+    fn fetch_feed(&self, url: &str) -> SourceResult<OpdsFeed> {
+        if let Some((data, _)) = self.network.cached(url) {
+            return parse_feed(&String::from_utf8_lossy(&data)).ok_or(SourceError::NotAFeed);
+        }
+        let data = self.fetch_data(url, FEED_ACCEPT)?;
+        let feed = parse_feed(&String::from_utf8_lossy(&data)).ok_or(SourceError::NotAFeed)?;
+        self.network.store(url, url, &data);
+        Ok(feed)
+    }
+
+    /// The search results feed for `query`, from the catalog's search link: a
+    /// template, or an OpenSearch description that holds one.
+    fn search_url(&self, query: &str) -> SourceResult<String> {
+        let catalog = self.catalog_url()?;
+        let root = self.fetch_feed(catalog)?;
+        let link = root.link("search").ok_or(SourceError::NoSearch)?;
+        let href = resolve_url(catalog, &link.href);
+        let template = if link.is_search_description() || !href.contains("{searchTerms}") {
+            let description = self.fetch_data(&href, SEARCH_ACCEPT)?;
+            let template = parse_search_description(&String::from_utf8_lossy(&description))
+                .ok_or(SourceError::NoSearch)?;
+            resolve_url(&href, &template)
+        } else {
+            href
+        };
+        Ok(fill_search_template(&template, query))
+    }
+
+    /// The feed shown when browsing: the one set in the settings, or the
+    /// catalog.
+    fn browse_url(&self) -> SourceResult<String> {
+        let browse = self.settings.browse_url.trim();
+        if browse.is_empty() {
+            Ok(String::from(self.catalog_url()?))
+        } else {
+            self.absolute_url(browse)
+        }
+    }
+
+    /// Fetches page `page` of a paginated feed by following its `next` links.
+    /// Returns the feed with the URL it was fetched from. Pages past the end
+    /// are empty.
+    fn feed_page(&self, url: &str, page: i32) -> SourceResult<(OpdsFeed, String)> {
+        let mut url = String::from(url);
+        let mut feed = self.fetch_feed(&url)?;
+        for _ in 1..page {
+            url = match feed.link("next") {
+                Some(link) => resolve_url(&url, &link.href),
+                None => return Ok((OpdsFeed::default(), url)),
+            };
+            feed = self.fetch_feed(&url)?;
+        }
+        Ok((feed, url))
+    }
+
+    /// An entry in a browse or search feed as a series: a feed of books, or a
+    /// single book.
+    fn entry_manga(&self, feed_url: &str, entry: &OpdsEntry) -> SourceResult<Option<MangaInfo>> {
+        let (path, id) = if let Some(link) = entry.navigation() {
+            let path = self.link_path(feed_url, link)?;
+            (path.clone(), path)
+        } else if let Some(link) = entry.acquisition() {
+            let path = self.link_path(feed_url, link)?;
+            let id = format!("{}{}", path, BOOK_FRAGMENT);
+            (path, id)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(MangaInfo {
+            id,
+            title: entry.title.clone(),
+            cover: entry
+                .thumbnail()
+                .map(|link| resolve_url(feed_url, &link.href))
+                .unwrap_or_default(),
+            author: entry.authors.join(", "),
+            description: entry.summary.clone(),
+            categories: entry.categories.clone(),
+            url: self.absolute_url(&path)?,
+            completed: entry.navigation().is_none(),
+        }))
+    }
+
+    /// Lists the series in the search results for `query`, or in the browse
+    /// feed without a query. Also says whether there is another page.
+    pub fn manga_list(
+        &self,
+        query: Option<&str>,
+        page: i32,
+    ) -> SourceResult<(Vec<MangaInfo>, bool)> {
+        let url = match query {
+            Some(query) => self.search_url(query)?,
+            None => self.browse_url()?,
+        };
+        let (feed, url) = self.feed_page(&url, page)?;
+        let mut mangas = Vec::new();
+        for entry in &feed.entries {
+            mangas.extend(self.entry_manga(&url, entry)?);
+        }
+        Ok((mangas, feed.link("next").is_some()))
+    }
+
+    /// The details of a series, from its feed. A book listed on its own has no
+    /// feed, so only what its download link gives is known, and Aidoku keeps
+    /// the title and cover it was listed with.
+    pub fn manga_details(&self, id: &str) -> SourceResult<MangaInfo> {
+        if let Some(path) = book_path(id) {
+            return Ok(MangaInfo {
+                id: String::from(id),
+                title: book_title(path),
+                url: self.absolute_url(path)?,
+                completed: true,
+                ..Default::default()
+            });
+        }
+
+        let url = self.absolute_url(id)?;
+        let feed = self.fetch_feed(&url)?;
+        let cover = feed
+            .entries
+            .iter()
+            .find_map(|entry| entry.thumbnail())
+            .map(|link| resolve_url(&url, &link.href))
+            .unwrap_or_default();
+        let mut authors: Vec<&str> = Vec::new();
+        for author in feed.entries.iter().flat_map(|entry| &entry.authors) {
+            if !authors.contains(&author.as_str()) {
+                authors.push(author);
+            }
+        }
+        Ok(MangaInfo {
+            id: String::from(id),
+            title: feed.title.clone(),
+            author: authors.join(", "),
+            cover,
+            url,
+            ..Default::default()
+        })
+    }
+
+    /// A book's chapter ID within its series: the entry's ID, or its download
+    /// link when it has none.
+    fn entry_chapter_id(&self, feed_url: &str, entry: &OpdsEntry) -> SourceResult<Option<String>> {
+        if !entry.id.is_empty() {
+            return Ok(Some(entry.id.clone()));
+        }
+        entry
+            .acquisition()
+            .or_else(|| entry.page_stream())
+            .map(|link| self.link_path(feed_url, link))
+            .transpose()
+    }
+
+    /// Calls `visit` with each entry of a series' feed and the URL of the page
+    /// it is on, following the feed's pages until `visit` returns `true`.
+    fn walk_series<F>(&self, id: &str, mut visit: F) -> SourceResult<()>
+    where
+        F: FnMut(&str, &OpdsFeed, &OpdsEntry) -> SourceResult<bool>,
+    {
+        let mut url = self.absolute_url(id)?;
+        for _ in 0..MAX_FEED_PAGES {
+            let feed = self.fetch_feed(&url)?;
+            for entry in &feed.entries {
+                if visit(&url, &feed, entry)? {
+                    return Ok(());
+                }
+            }
+            url = match feed.link("next") {
+                Some(link) => resolve_url(&url, &link.href),
+                None => break,
+            };
+        }
+        Ok(())
+    }
+
+    /// Lists the books of a series, newest first. A book listed on its own is
+    /// its only chapter.
+    pub fn chapter_list(&self, id: &str) -> SourceResult<Vec<ChapterInfo>> {
+        if let Some(path) = book_path(id) {
+            return Ok(vec![ChapterInfo {
+                id: String::from(id),
+                title: book_title(path),
+                chapter: 1.0,
+                volume: -1.0,
+                date_updated: UNKNOWN_DATE,
+                url: self.absolute_url(path)?,
+                ..Default::default()
+            }]);
+        }
+
+        let mut chapters = Vec::new();
+        self.walk_series(id, |url, feed, entry| {
+            // Subfolders such as Kavita's volumes are left to their own feed.
+            let file = match entry.acquisition().or_else(|| entry.page_stream()) {
+                Some(link) => link,
+                None => return Ok(false),
+            };
+            let chapter_id = match self.entry_chapter_id(url, entry)? {
+                Some(chapter_id) => chapter_id,
+                None => return Ok(false),
+            };
+            let info = parse_filename(&entry.title, &feed.title);
+            chapters.push(ChapterInfo {
+                id: chapter_id,
+                title: entry.title.clone(),
+                chapter: info.ordered_chapter().unwrap_or(-1.0),
+                volume: info.volume.unwrap_or(-1.0),
+                date_updated: parse_date(&entry.updated, self.settings.now).unwrap_or(UNKNOWN_DATE),
+                scanlator: info.group.unwrap_or_default(),
+                url: resolve_url(url, &file.href),
+            });
+            Ok(false)
+        })?;
+        // Feeds list books oldest first.
+        chapters.reverse();
+        Ok(chapters)
+    }
+
+    /// Lists the pages of a book. An archive too large to read is shown as a
+    /// page saying so, since Aidoku only shows that the chapter failed.
+    pub fn page_list(&self, manga_id: &str, chapter_id: &str) -> SourceResult<Vec<PageInfo>> {
+        match self.book_page_list(manga_id, chapter_id) {
+            Err(error @ SourceError::ArchiveTooLarge(_)) => Ok(vec![PageInfo {
+                index: 0,
+                source: PageSource::Text(error.message()),
+            }]),
+            pages => pages,
+        }
+    }
+
+    /// Lists the pages of a book, looked up by its chapter ID in the series'
+    /// feed. A book listed on its own is read from its download link.
+    fn book_page_list(&self, manga_id: &str, chapter_id: &str) -> SourceResult<Vec<PageInfo>> {
+        if let Some(path) = book_path(manga_id) {
+            let url = self.absolute_url(path)?;
+            return self.book_pages(None, &url, "");
+        }
+
+        let mut found = None;
+        self.walk_series(manga_id, |url, _, entry| {
+            if self.entry_chapter_id(url, entry)?.as_deref() != Some(chapter_id) {
+                return Ok(false);
+            }
+            let file = entry
+                .acquisition()
+                .map(|link| (resolve_url(url, &link.href), link.kind.clone()));
+            found = Some((entry_stream(url, entry), file));
+            Ok(true)
+        })?;
+        match found {
+            Some((stream, Some((file_url, kind)))) => self.book_pages(stream, &file_url, &kind),
+            Some((Some(stream), None)) => self.book_pages(Some(stream), "", ""),
+            _ => Err(SourceError::NotFound(String::from(chapter_id))),
+        }
+    }
+
+    /// Lists a book's pages: streamed one at a time with OPDS-PSE when the
+    /// server offers it, or else read from the downloaded archive.
+    fn book_pages(
+        &self,
+        stream: Option<(String, u32)>,
+        file_url: &str,
+        kind: &str,
+    ) -> SourceResult<Vec<PageInfo>> {
+        if let Some((template, count)) = stream.filter(|(_, count)| *count > 0) {
+            let link = OpdsLink {
+                href: template,
+                ..Default::default()
+            };
+            return Ok((0..count)
+                .map(|index| PageInfo {
+                    index: index as i32,
+                    source: PageSource::Url(link.page_url(index)),
+                })
+                .collect());
+        }
+        if let Some(format) = document_format(file_url, kind) {
+            return Err(SourceError::UnsupportedFormat(format));
+        }
+        self.book_archive_pages(file_url)
+    }
+
+    /// Reads a book's page images straight from its archive with range
+    /// requests, in reading order.
+    fn book_archive_pages(&self, url: &str) -> SourceResult<Vec<PageInfo>> {
+        let images = match archive_pages(&self.network, url, self.settings.skip_credit_pages) {
+            Ok(images) => images,
+            Err(ArchiveError::NoPages) => return Err(SourceError::NoPages),
+            Err(ArchiveError::Status(code)) => {
+                return Err(check_status(code, url)
+                    .err()
+                    .unwrap_or(SourceError::Http(code)))
+            }
+            Err(ArchiveError::Zip(error)) => return Err(SourceError::Archive(error)),
+            Err(ArchiveError::Truncated) => return Err(SourceError::Archive(ZipError::Malformed)),
+            Err(ArchiveError::TooLarge(size)) => return Err(SourceError::ArchiveTooLarge(size)),
+        };
+        Ok(images
+            .into_iter()
+            .enumerate()
+            .map(|(index, image)| PageInfo {
+                index: index as i32,
+                source: PageSource::Image(image),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stored_zip, FixtureNetwork};
+
+    const KOMGA: &str = "https://komga.example.com/opds/v1.2/catalog";
+    const BERSERK: &str = "/opds/v1.2/series/0B3F5V/";
+    const CALIBRE: &str = "https://books.example.com/opds";
+
+    fn catalog(catalog_url: &str, network: FixtureNetwork) -> Catalog<FixtureNetwork> {
+        let settings = Settings {
+            catalog_url: String::from(catalog_url),
+            now: 1_710_000_000.0,
+            ..Default::default()
+        };
+        Catalog::new(network, settings)
+    }
+
+    fn komga() -> Catalog<FixtureNetwork> {
+        let network = FixtureNetwork::default()
+            .page(
+                "https://komga.example.com/opds/v1.2/series/0B3F5V/",
+                include_bytes!("../fixtures/komga_series.xml"),
+            )
+            .page(
+                "https://komga.example.com/opds/v1.2/series/0B3F5V/?page=1",
+                br#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Berserk</title>
+                    <entry><title>Berserk v03</title>
+                    <link rel="http://opds-spec.org/acquisition" type="application/zip"
+                          href="/opds/v1.2/books/0B3F6W8V1R2QZ/file/Berserk%20v03.cbz"/></entry>
+                    </feed>"#,
+            );
+        catalog(KOMGA, network)
+    }
+
+    fn calibre() -> Catalog<FixtureNetwork> {
+        let network = FixtureNetwork::default()
+            .page(CALIBRE, include_bytes!("../fixtures/calibre_books.xml"))
+            .page(
+                "https://books.example.com/opds/osd",
+                include_bytes!("../fixtures/calibre_osd.xml"),
+            )
+            .page(
+                "https://books.example.com/opds/search/yotsuba",
+                include_bytes!("../fixtures/calibre_books.xml"),
+            );
+        catalog(CALIBRE, network)
+    }
+
+    #[test]
+    fn test_not_configured() {
+        let catalog = catalog("", FixtureNetwork::default());
+        assert_eq!(catalog.manga_list(None, 1), Err(SourceError::NotConfigured));
+        assert!(catalog.network().requests().is_empty());
+    }
+
+    #[test]
+    fn test_books_listed_on_their_own() {
+        let catalog = calibre();
+        let (mangas, has_more) = catalog.manga_list(None, 1).unwrap();
+        assert!(!has_more);
+        assert_eq!(mangas.len(), 2);
+        // The ID is the download link's path alone.
+        assert_eq!(mangas[0].id, "/opds/download/12/epub/#book");
+        assert_eq!(mangas[0].title, "Spice and Wolf, Vol. 1");
+        assert_eq!(mangas[0].cover, "https://books.example.com/opds/cover/12");
+        assert_eq!(mangas[0].author, "Isuna Hasekura, Jyuu Ayakura");
+        assert_eq!(
+            mangas[0].url,
+            "https://books.example.com/opds/download/12/epub/"
+        );
+        assert!(mangas[0].completed);
+        assert_eq!(mangas[1].id, "/opds/download/7/cbz/#book");
+
+        let details = catalog.manga_details(&mangas[1].id).unwrap();
+        assert_eq!(
+            details.url,
+            "https://books.example.com/opds/download/7/cbz/"
+        );
+        assert!(details.completed);
+        let chapters = catalog.chapter_list(&mangas[1].id).unwrap();
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].id, mangas[1].id);
+        assert_eq!(chapters[0].chapter, 1.0);
+
+        // Calibre-web names the format in the path.
+        assert_eq!(
+            catalog.page_list(&mangas[0].id, &mangas[0].id),
+            Err(SourceError::UnsupportedFormat("EPUB"))
+        );
+    }
+
+    #[test]
+    fn test_old_book_ids() {
+        let catalog = calibre();
+        let id = "/opds/download/7/Yotsuba%20v01.cbz#title=Yotsuba&cover=";
+        let details = catalog.manga_details(id).unwrap();
+        assert_eq!(details.title, "Yotsuba v01");
+        assert_eq!(
+            details.url,
+            "https://books.example.com/opds/download/7/Yotsuba%20v01.cbz"
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let catalog = calibre();
+        let (mangas, _) = catalog.manga_list(Some("yotsuba"), 1).unwrap();
+        assert_eq!(mangas.len(), 2);
+        assert_eq!(
+            catalog.network().requests(),
+            [
+                CALIBRE,
+                "https://books.example.com/opds/osd",
+                "https://books.example.com/opds/search/yotsuba"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_navigation_entries() {
+        let network = FixtureNetwork::default().page(
+            "https://kavita.example.com/api/opds/0a1b2c",
+            include_bytes!("../fixtures/kavita_root.xml"),
+        );
+        let catalog = catalog("https://kavita.example.com/api/opds/0a1b2c", network);
+        let (mangas, _) = catalog.manga_list(None, 1).unwrap();
+        let ids: Vec<&str> = mangas.iter().map(|manga| manga.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "/api/opds/0a1b2c/on-deck",
+                "/api/opds/0a1b2c/libraries",
+                "/api/opds/0a1b2c/libraries/2"
+            ]
+        );
+        assert!(!mangas[0].completed);
+        // Pages past the end are empty.
+        let (mangas, has_more) = catalog.manga_list(None, 2).unwrap();
+        assert!(mangas.is_empty());
+        assert!(!has_more);
+    }
+
+    #[test]
+    fn test_series() {
+        let catalog = komga();
+        let details = catalog.manga_details(BERSERK).unwrap();
+        assert_eq!(details.title, "Berserk");
+        assert_eq!(
+            details.cover,
+            "https://komga.example.com/opds/v1.2/books/0B3F6W8V1R2QX/thumbnail/small"
+        );
+
+        let chapters = catalog.chapter_list(BERSERK).unwrap();
+        let ids: Vec<&str> = chapters.iter().map(|chapter| chapter.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "/opds/v1.2/books/0B3F6W8V1R2QZ/file/Berserk%20v03.cbz",
+                "0B3F6W8V1R2QY",
+                "0B3F6W8V1R2QX"
+            ]
+        );
+        assert_eq!(chapters[2].volume, 1.0);
+        assert_eq!(chapters[2].date_updated, 1_707_599_024.0);
+        // Books without a date aren't shown as uploaded just now.
+        assert_eq!(chapters[0].date_updated, UNKNOWN_DATE);
+        assert_eq!(
+            chapters[0].url,
+            "https://komga.example.com/opds/v1.2/books/0B3F6W8V1R2QZ/file/Berserk%20v03.cbz"
+        );
+    }
+
+    #[test]
+    fn test_streamed_pages() {
+        let catalog = komga();
+        let pages = catalog.page_list(BERSERK, "0B3F6W8V1R2QY").unwrap();
+        assert_eq!(pages.len(), 208);
+        assert_eq!(
+            pages[1].source,
+            PageSource::Url(String::from(
+                "https://komga.example.com/opds/v1.2/books/0B3F6W8V1R2QY/pages/1?zero_based=true"
+            ))
+        );
+        assert_eq!(
+            catalog.page_list(BERSERK, "missing"),
+            Err(SourceError::NotFound(String::from("missing")))
+        );
+    }
+
+    #[test]
+    fn test_archive_pages() {
+        let book = "https://komga.example.com/opds/v1.2/books/0B3F6W8V1R2QZ/file/Berserk%20v03.cbz";
+        let catalog = komga();
+        let id = "/opds/v1.2/books/0B3F6W8V1R2QZ/file/Berserk%20v03.cbz";
+        assert_eq!(
+            catalog.page_list(BERSERK, id),
+            Err(SourceError::NotFound(String::from(book)))
+        );
+
+        let mut catalog = komga();
+        catalog.network = catalog
+            .network
+            .page(book, include_bytes!("../fixtures/sample.cbz"));
+        let pages = catalog.page_list(BERSERK, id).unwrap();
+        assert!(!pages.is_empty());
+        assert!(matches!(pages[0].source, PageSource::Image(_)));
+    }
+
+    #[test]
+    fn test_archive_without_pages() {
+        let book = "https://books.example.com/opds/download/7/cbz/";
+        let mut catalog = calibre();
+        // An empty ZIP archive: just the end of central directory record.
+        let mut empty = vec![0x50, 0x4b, 0x05, 0x06];
+        empty.extend([0; 18]);
+        catalog.network = catalog.network.page(book, &empty);
+        let id = "/opds/download/7/cbz/#book";
+        assert_eq!(catalog.page_list(id, id), Err(SourceError::NoPages));
+    }
+
+    #[test]
+    fn test_archive_too_large() {
+        let book = "https://books.example.com/opds/download/7/cbz/";
+        let mut catalog = calibre();
+        catalog.network = catalog.network.page(
+            book,
+            &stored_zip(&[
+                ("01.jpg", vec![1; 10], 40 << 20),
+                ("02.jpg", vec![2; 10], 40 << 20),
+            ]),
+        );
+        let id = "/opds/download/7/cbz/#book";
+        let pages = catalog.page_list(id, id).unwrap();
+        assert_eq!(pages.len(), 1);
+        match &pages[0].source {
+            PageSource::Text(text) => assert!(text.contains("holds 80.0 MB of pages")),
+            source => panic!("expected the size limit, got {:?}", source),
+        }
+    }
+
+    #[test]
+    fn test_document_format() {
+        assert_eq!(document_format("/file", "application/pdf"), Some("PDF"));
+        assert_eq!(
+            document_format("/books/Spice%20and%20Wolf.epub?key=1", ""),
+            Some("EPUB")
+        );
+        assert_eq!(document_format("/opds/download/12/mobi/", ""), Some("MOBI"));
+        assert_eq!(document_format("/opds/download/7/cbz/", ""), None);
+        assert_eq!(
+            document_format("/books/Berserk v01.cbz", "application/zip"),
+            None
+        );
+        assert_eq!(document_format("/opds/v1.2/books/0B3F/file", ""), None);
+    }
+}
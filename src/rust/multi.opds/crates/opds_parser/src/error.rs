@@ -0,0 +1,88 @@
+use alloc::{format, string::String};
+
+use crate::{format_size, ZipError, MAX_ARCHIVE_PAGES_SIZE};
+
+pub type SourceResult<T> = core::result::Result<T, SourceError>;
+
+/// Why a catalog feed or book couldn't be fetched or read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    /// No catalog URL has been set up yet.
+    NotConfigured,
+    /// The server wants credentials, or rejected the ones it was given.
+    Unauthorized,
+    /// The feed or book doesn't exist, usually because it was removed.
+    NotFound(String),
+    /// The server itself is failing (HTTP 5xx).
+    Server(i32),
+    /// Any other unexpected HTTP status.
+    Http(i32),
+    /// The response wasn't an OPDS feed.
+    NotAFeed,
+    /// The catalog has no search.
+    NoSearch,
+    /// The book's archive couldn't be read.
+    Archive(ZipError),
+    /// The book's archive holds no page images.
+    NoPages,
+    /// The book is a file Aidoku can't show, such as a PDF or EPUB.
+    UnsupportedFormat(&'static str),
+    /// The book's archive holds more page data, in bytes, than can be read.
+    ArchiveTooLarge(u64),
+}
+
+impl SourceError {
+    /// What went wrong, for the Aidoku log. Paths are left out, since Kavita
+    /// carries its API key in them.
+    pub fn message(&self) -> String {
+        match self {
+            SourceError::NotConfigured => String::from("No OPDS catalog address is set up"),
+            SourceError::Unauthorized => String::from(
+                "The OPDS server rejected the login, check the username and password in the \
+                 source settings",
+            ),
+            SourceError::NotFound(_) => String::from(
+                "The feed or book was not found on the OPDS server, it may have been removed",
+            ),
+            SourceError::Server(code) => format!(
+                "The OPDS server is having trouble (HTTP {}), try again later",
+                code
+            ),
+            SourceError::Http(code) => {
+                format!("Unexpected response from the OPDS server (HTTP {})", code)
+            }
+            SourceError::NotAFeed => {
+                String::from("The catalog address doesn't lead to an OPDS feed")
+            }
+            SourceError::NoSearch => String::from("The OPDS catalog has no search"),
+            SourceError::Archive(error) => match error {
+                ZipError::Malformed => {
+                    String::from("The book's archive is damaged or not a ZIP file")
+                }
+                ZipError::UnsupportedMethod(method) => format!(
+                    "The book's archive uses an unsupported compression method ({})",
+                    method
+                ),
+                ZipError::Encrypted => String::from("The book's archive is password protected"),
+                ZipError::Corrupt => String::from("A page in the book's archive is corrupt"),
+            },
+            SourceError::NoPages => String::from("The book's archive holds no page images"),
+            SourceError::UnsupportedFormat(format) => {
+                format!("{} files can't be read in Aidoku", format)
+            }
+            SourceError::ArchiveTooLarge(size) => format!(
+                "This book's archive holds {} of pages, and only archives of up to {} can be \
+                 read. Read it in your server's web reader instead, or enable page streaming \
+                 (OPDS-PSE) on the server.",
+                format_size(*size),
+                format_size(MAX_ARCHIVE_PAGES_SIZE)
+            ),
+        }
+    }
+}
+
+impl From<ZipError> for SourceError {
+    fn from(error: ZipError) -> Self {
+        SourceError::Archive(error)
+    }
+}
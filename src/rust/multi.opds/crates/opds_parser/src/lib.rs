@@ -0,0 +1,9 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod catalog;
+mod error;
+pub use catalog::*;
+pub use error::*;
+pub use source_common::*;
@@ -0,0 +1,305 @@
+//! Reads a book's page images straight from its ZIP archive on the server
+//! with range requests, so only the directory and the pages are downloaded.
+
+use alloc::{format, vec, vec::Vec};
+
+use crate::{
+    find_end_of_archive, is_credit_page, is_page_image, natural_cmp, parse_central_directory,
+    parse_content_range, parse_zip64_end, EndOfArchive, Network, ZipEntry, ZipError,
+    ARCHIVE_ACCEPT, TAIL_LEN,
+};
+
+/// The length of a ZIP64 end of central directory record.
+const ZIP64_END_LEN: u64 = 56;
+/// Extra bytes fetched with each entry in case its local header has a longer
+/// extra field than the central directory records.
+const LOCAL_EXTRA_SLACK: u64 = 1024;
+/// The most bytes fetched by one range request. Neighbouring entries are read
+/// together up to this size, so a chapter takes a few requests, not one per
+/// page.
+const MAX_REQUEST_LEN: u64 = 8 << 20;
+/// How many bytes of other entries, such as skipped credit pages, may sit
+/// between two page images that are read with one request.
+const MAX_REQUEST_GAP: u64 = 256 << 10;
+/// The most page data read from one archive. Every page is held in memory and
+/// handed to Aidoku as base64, so larger archives, such as whole volume packs,
+/// aren't read. The sources show this limit in place of the pages. Reading
+/// pages only when they're shown would need a request per page to find where
+/// its data starts, since Aidoku can't be given a byte range of the archive.
+pub const MAX_ARCHIVE_PAGES_SIZE: u64 = 64 << 20;
+
+/// Why pages couldn't be read from an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    /// The server answered a range request with this HTTP status.
+    Status(i32),
+    /// The archive itself couldn't be read.
+    Zip(ZipError),
+    /// The archive is shorter than its directory says.
+    Truncated,
+    /// The archive holds no page images.
+    NoPages,
+    /// The archive holds more page data, in bytes, than
+    /// `MAX_ARCHIVE_PAGES_SIZE`.
+    TooLarge(u64),
+}
+
+impl From<ZipError> for ArchiveError {
+    fn from(error: ZipError) -> Self {
+        ArchiveError::Zip(error)
+    }
+}
+
+/// A piece of an archive, and where it starts in the whole file.
+struct Range {
+    data: Vec<u8>,
+    start: u64,
+}
+
+impl Range {
+    /// The bytes from `start` to `start + len` of the archive, if this range
+    /// holds all of them.
+    fn slice(&self, start: u64, len: u64) -> Option<&[u8]> {
+        let from = usize::try_from(start.checked_sub(self.start)?).ok()?;
+        let to = from.checked_add(usize::try_from(len).ok()?)?;
+        self.data.get(from..to)
+    }
+
+    /// Everything this range holds from `start` on.
+    fn rest_from(&self, start: u64) -> Option<&[u8]> {
+        let from = usize::try_from(start.checked_sub(self.start)?).ok()?;
+        self.data.get(from..)
+    }
+}
+
+/// The archive at `url`, read over `network`.
+struct Archive<'a, N: Network> {
+    network: &'a N,
+    url: &'a str,
+}
+
+impl<N: Network> Archive<'_, N> {
+    /// Fetches part of the archive. `range` is a `Range` header value, either
+    /// `bytes=start-end` or `bytes=-length` for the end of the file.
+    fn fetch_range(&self, range: &str) -> Result<Range, ArchiveError> {
+        let response = self
+            .network
+            .get(self.url, &[("Accept", ARCHIVE_ACCEPT), ("Range", range)]);
+        let code = response.status;
+        if !(200..=299).contains(&code) {
+            return Err(ArchiveError::Status(code));
+        }
+        let start = response
+            .header("Content-Range")
+            .and_then(parse_content_range)
+            .map(|(start, _)| start);
+        let data = response.body;
+        match start {
+            Some(start) if code == 206 => Ok(Range { data, start }),
+            // The server ignored the range and sent the whole file.
+            _ => Ok(Range { data, start: 0 }),
+        }
+    }
+
+    /// Reads `len` bytes at `start`, from `cached` when it already holds them.
+    fn read(&self, cached: &Range, start: u64, len: u64) -> Result<Vec<u8>, ArchiveError> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        if let Some(data) = cached.slice(start, len) {
+            return Ok(Vec::from(data));
+        }
+        let range = self.fetch_range(&format!("bytes={}-{}", start, start + len - 1))?;
+        // Entry spans can run past the end of the file, which servers cut short.
+        range
+            .slice(start, len)
+            .or_else(|| range.rest_from(start))
+            .map(Vec::from)
+            .ok_or(ArchiveError::Truncated)
+    }
+
+    /// Lists the page images in the archive in reading order, from its
+    /// central directory.
+    fn page_entries(
+        &self,
+        tail: &Range,
+        skip_credits: bool,
+    ) -> Result<Vec<ZipEntry>, ArchiveError> {
+        let directory = match find_end_of_archive(&tail.data, tail.start)? {
+            EndOfArchive::Found(directory) => directory,
+            EndOfArchive::Zip64At(offset) => {
+                parse_zip64_end(&self.read(tail, offset, ZIP64_END_LEN)?)?
+            }
+        };
+        let listing = self.read(tail, directory.offset, directory.size)?;
+        let mut entries: Vec<ZipEntry> = parse_central_directory(&listing)?
+            .into_iter()
+            .filter(|entry| !entry.is_dir() && is_page_image(&entry.name))
+            .filter(|entry| !skip_credits || !is_credit_page(&entry.name))
+            .collect();
+        entries.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+        Ok(entries)
+    }
+}
+
+/// Reads the page images of the ZIP archive at `url` with range requests, in
+/// reading order, leaving out credit pages with `skip_credits`. Each image is
+/// extracted and returned as it is.
+pub fn archive_pages<N: Network>(
+    network: &N,
+    url: &str,
+    skip_credits: bool,
+) -> Result<Vec<Vec<u8>>, ArchiveError> {
+    let archive = Archive { network, url };
+    let tail = archive.fetch_range(&format!("bytes=-{}", TAIL_LEN))?;
+    let entries = archive.page_entries(&tail, skip_credits)?;
+    if entries.is_empty() {
+        return Err(ArchiveError::NoPages);
+    }
+    let size = entries.iter().map(|entry| entry.uncompressed_size).sum();
+    if size > MAX_ARCHIVE_PAGES_SIZE {
+        return Err(ArchiveError::TooLarge(size));
+    }
+
+    // Entries are fetched in the order they're stored, in as few requests as
+    // possible, and put back in reading order.
+    let mut stored_order: Vec<usize> = (0..entries.len()).collect();
+    stored_order.sort_by_key(|&index| entries[index].header_offset);
+    let mut images = vec![Vec::new(); entries.len()];
+    let mut batch_start = 0;
+    while batch_start < stored_order.len() {
+        let first = &entries[stored_order[batch_start]];
+        let start = first.header_offset;
+        let mut end = start + first.span(LOCAL_EXTRA_SLACK);
+        let mut batch_end = batch_start + 1;
+        while let Some(&index) = stored_order.get(batch_end) {
+            let entry = &entries[index];
+            let entry_end = entry.header_offset + entry.span(LOCAL_EXTRA_SLACK);
+            if entry.header_offset > end + MAX_REQUEST_GAP || entry_end - start > MAX_REQUEST_LEN {
+                break;
+            }
+            end = end.max(entry_end);
+            batch_end += 1;
+        }
+
+        let batch = Range {
+            data: archive.read(&tail, start, end - start)?,
+            start,
+        };
+        for &index in &stored_order[batch_start..batch_end] {
+            let entry = &entries[index];
+            let local = batch
+                .slice(entry.header_offset, entry.span(LOCAL_EXTRA_SLACK))
+                .or_else(|| batch.rest_from(entry.header_offset))
+                .ok_or(ArchiveError::Truncated)?;
+            images[index] = entry.extract(local)?;
+        }
+        batch_start = batch_end;
+    }
+    Ok(images)
+}
+
+/// Builds a ZIP archive of stored entries, for tests. Each entry can claim a
+/// larger size than it holds.
+#[cfg(any(test, feature = "fixtures"))]
+pub fn stored_zip(files: &[(&str, Vec<u8>, u32)]) -> Vec<u8> {
+    fn put16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    fn put32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data, claimed_size) in files {
+        let offset = archive.len() as u32;
+        put32(&mut archive, 0x0403_4b50);
+        for value in [20, 0, 0, 0, 0] {
+            put16(&mut archive, value);
+        }
+        put32(&mut archive, 0);
+        put32(&mut archive, data.len() as u32);
+        put32(&mut archive, data.len() as u32);
+        put16(&mut archive, name.len() as u16);
+        put16(&mut archive, 0);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        put32(&mut directory, 0x0201_4b50);
+        for value in [20, 20, 0, 0, 0, 0] {
+            put16(&mut directory, value);
+        }
+        put32(&mut directory, 0);
+        put32(&mut directory, data.len() as u32);
+        put32(&mut directory, *claimed_size);
+        put16(&mut directory, name.len() as u16);
+        for value in [0, 0, 0, 0] {
+            put16(&mut directory, value);
+        }
+        put32(&mut directory, 0);
+        put32(&mut directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    put32(&mut archive, 0x0605_4b50);
+    put16(&mut archive, 0);
+    put16(&mut archive, 0);
+    put16(&mut archive, files.len() as u16);
+    put16(&mut archive, files.len() as u16);
+    put32(&mut archive, directory.len() as u32);
+    put32(&mut archive, directory_offset);
+    put16(&mut archive, 0);
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixtureNetwork;
+    use alloc::string::String;
+
+    const URL: &str = "https://example.com/books/Test c001.zip";
+
+    fn page(name: &str, byte: u8, len: usize) -> (&str, Vec<u8>, u32) {
+        (name, vec![byte; len], len as u32)
+    }
+
+    #[test]
+    fn test_batched_reads() {
+        let network = FixtureNetwork::default().page(
+            URL,
+            &stored_zip(&[
+                page("02.jpg", 2, 100_000),
+                page("01.jpg", 1, 100_000),
+                // Skipped, and too large to read past, so the pages after it
+                // are fetched separately.
+                page("zz_credits.jpg", 0, 300_000),
+                page("03.jpg", 3, 100_000),
+                page("04.jpg", 4, 100_000),
+            ]),
+        );
+        let pages = archive_pages(&network, URL, true).unwrap();
+        let firsts: Vec<u8> = pages.iter().map(|page| page[0]).collect();
+        assert_eq!(firsts, [1, 2, 3, 4]);
+        // The end of the archive, then one request for each run of pages.
+        assert_eq!(network.requests(), vec![String::from(URL); 3]);
+    }
+
+    #[test]
+    fn test_too_large() {
+        let network = FixtureNetwork::default().page(
+            URL,
+            &stored_zip(&[
+                ("01.jpg", vec![1; 10], 40 << 20),
+                ("02.jpg", vec![2; 10], 40 << 20),
+            ]),
+        );
+        assert_eq!(
+            archive_pages(&network, URL, false),
+            Err(ArchiveError::TooLarge(80 << 20))
+        );
+        // Only the directory was read.
+        assert_eq!(network.requests().len(), 1);
+    }
+}
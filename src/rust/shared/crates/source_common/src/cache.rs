@@ -0,0 +1,54 @@
+use aidoku::std::{current_date, String, Vec};
+use core::ptr::addr_of_mut;
+
+/// How many pages are kept at once.
+const CACHE_SIZE: usize = 8;
+/// How long a cached page stays fresh, in seconds.
+const CACHE_TTL: f64 = 120.0;
+
+struct CachedPage {
+    url: String,
+    /// Where the page ended up after redirects.
+    location: String,
+    fetched: f64,
+    data: Vec<u8>,
+}
+
+static mut PAGE_CACHE: Vec<CachedPage> = Vec::new();
+
+fn pages() -> &'static mut Vec<CachedPage> {
+    unsafe { &mut *addr_of_mut!(PAGE_CACHE) }
+}
+
+/// Returns the body and final location of a page fetched within the last
+/// `CACHE_TTL` seconds, so opening a series and then its chapters doesn't
+/// fetch the same folder or feed twice.
+pub fn get(url: &str) -> Option<(Vec<u8>, String)> {
+    let now = current_date();
+    let pages = pages();
+    pages.retain(|page| now - page.fetched < CACHE_TTL);
+    pages
+        .iter()
+        .find(|page| page.url == url)
+        .map(|page| (page.data.clone(), page.location.clone()))
+}
+
+/// Stores the body of a page, dropping the oldest one when the cache is full.
+pub fn insert(url: &str, location: &str, data: &[u8]) {
+    let pages = pages();
+    pages.retain(|page| page.url != url);
+    if pages.len() >= CACHE_SIZE {
+        pages.remove(0);
+    }
+    pages.push(CachedPage {
+        url: String::from(url),
+        location: String::from(location),
+        fetched: current_date(),
+        data: data.to_vec(),
+    });
+}
+
+/// Forgets every cached page, used when the server or credentials change.
+pub fn clear() {
+    pages().clear();
+}
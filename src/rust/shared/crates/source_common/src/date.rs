@@ -0,0 +1,263 @@
+use alloc::vec::Vec;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 60.0 * MINUTE;
+const DAY: f64 = 24.0 * HOUR;
+
+/// Days between 1970-01-01 and the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let prefix = name.get(..3)?;
+    MONTHS
+        .iter()
+        .position(|month| *month == prefix)
+        .map(|i| i as u32 + 1)
+}
+
+/// Seconds since the Unix epoch for a UTC date and time, or `None` if any
+/// field is out of range.
+fn timestamp(year: i64, month: u32, day: u32, time: (u32, u32, u32)) -> Option<f64> {
+    let (hour, minute, second) = time;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let days = days_from_civil(year, month, day) as f64;
+    Some(days * DAY + hour as f64 * HOUR + minute as f64 * MINUTE + second as f64)
+}
+
+/// Splits a date into runs of digits and runs of letters, keeping the
+/// punctuation between them so times and offsets can be told apart.
+fn fields(input: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (i, c) in input.char_indices() {
+        let class = if c.is_ascii_digit() {
+            0
+        } else if c.is_alphabetic() {
+            1
+        } else if c.is_whitespace() || c == ',' {
+            2
+        } else {
+            3 + i
+        };
+        if previous.is_some_and(|previous| previous != class) {
+            fields.push(&input[start..i]);
+            start = i;
+        }
+        previous = Some(class);
+    }
+    if start < input.len() {
+        fields.push(&input[start..]);
+    }
+    fields.retain(|field| !field.trim_matches([' ', ',']).is_empty());
+    fields
+}
+
+/// Reads `HH:mm`, `HH:mm:ss` and an optional `am`/`pm` and UTC offset from
+/// the fields following a date. Returns the time of day and the offset in
+/// seconds.
+fn parse_time(fields: &[&str]) -> Option<((u32, u32, u32), f64)> {
+    let mut numbers = Vec::new();
+    let mut offset = 0.0;
+    let mut meridiem = None;
+    let mut i = 0;
+    while i < fields.len() {
+        match fields[i] {
+            ":" | "." if !numbers.is_empty() => {}
+            "t" | "z" | "utc" | "gmt" => {}
+            "am" => meridiem = Some(0),
+            "pm" => meridiem = Some(12),
+            sign @ ("+" | "-") if !numbers.is_empty() => {
+                let hours: u32 = fields.get(i + 1)?.parse().ok()?;
+                let (hours, minutes) = match fields.get(i + 3) {
+                    Some(minutes) if fields[i + 2] == ":" => (hours, minutes.parse().ok()?),
+                    _ if hours >= 100 => (hours / 100, hours % 100),
+                    _ => (hours, 0),
+                };
+                offset = hours as f64 * HOUR + minutes as f64 * MINUTE;
+                if sign == "-" {
+                    offset = -offset;
+                }
+                break;
+            }
+            field if field.bytes().all(|b| b.is_ascii_digit()) && numbers.len() < 3 => {
+                numbers.push(field.parse::<u32>().ok()?)
+            }
+            _ => return None,
+        }
+        i += 1;
+    }
+    let (mut hour, minute, second) = match numbers[..] {
+        [] => (0, 0, 0),
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return None,
+    };
+    if let Some(meridiem) = meridiem {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour = hour % 12 + meridiem;
+    }
+    Some(((hour, minute, second), offset))
+}
+
+/// Reads "3 hours ago", "an hour ago", "just now", "today" and "yesterday".
+fn parse_relative(input: &str, now: f64) -> Option<f64> {
+    match input {
+        "now" | "just now" | "today" => return Some(now),
+        "yesterday" => return Some(now - DAY),
+        _ => {}
+    }
+    let mut words = input.strip_suffix(" ago")?.split_whitespace();
+    let amount = match words.next()? {
+        "a" | "an" | "one" => 1.0,
+        number => number.parse::<f64>().ok()?,
+    };
+    let unit = match words.next()?.trim_end_matches('s') {
+        "sec" | "second" => 1.0,
+        "min" | "minute" => MINUTE,
+        "hr" | "hour" => HOUR,
+        "day" => DAY,
+        "week" => 7.0 * DAY,
+        "month" => 30.0 * DAY,
+        "year" => 365.0 * DAY,
+        _ => return None,
+    };
+    words.next().is_none().then_some(now - amount * unit)
+}
+
+/// Parses an upload time, as shown in a Madokami file listing or an OPDS feed,
+/// into seconds since the Unix epoch.
+///
+/// Absolute times are read as UTC unless they carry their own offset, and can
+/// be written as `2023-04-05 06:07`, `2023-04-05T06:07:08Z`, `2023/04/05`,
+/// `05-Apr-2023 06:07`, `5 April 2023` or `April 5, 2023 6:07 PM`. Relative
+/// times such as `3 hours ago` or `yesterday` count back from `now`.
+pub fn parse_date(input: &str, now: f64) -> Option<f64> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return None;
+    }
+    if let Some(date) = parse_relative(&input, now) {
+        return Some(date);
+    }
+    let fields = fields(&input);
+    let number = |i: usize| -> Option<u32> {
+        let field: &str = fields.get(i)?;
+        field
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| field.parse().ok())?
+    };
+    let separated = |i: usize| matches!(fields.get(i), Some(&("-" | "/" | ".")));
+
+    let (year, month, day, rest) = if fields.first()?.len() == 4 && separated(1) && separated(3) {
+        // 2023-04-05, 2023/04/05
+        (number(0)? as i64, number(2)?, number(4)?, 5)
+    } else if separated(1) && separated(3) && month_number(fields.get(2)?).is_some() {
+        // 05-Apr-2023
+        (number(4)? as i64, month_number(fields[2])?, number(0)?, 5)
+    } else if let Some(month) = month_number(fields.first()?) {
+        // April 5, 2023
+        (number(2)? as i64, month, number(1)?, 3)
+    } else if let Some(month) = fields.get(1).and_then(|field| month_number(field)) {
+        // 5 April 2023
+        (number(2)? as i64, month, number(0)?, 3)
+    } else {
+        return None;
+    };
+    let (time, offset) = parse_time(&fields[rest..])?;
+    timestamp(year, month, day, time).map(|date| date - offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2023-04-05 06:07:08 UTC
+    const NOW: f64 = 1_680_674_828.0;
+
+    #[test]
+    fn test_listing_format() {
+        assert_eq!(parse_date("2023-04-05 06:07", 0.0), Some(NOW - 8.0));
+        assert_eq!(parse_date(" 2023-04-05 06:07:08 ", 0.0), Some(NOW));
+        assert_eq!(parse_date("1970-01-01 00:00", 0.0), Some(0.0));
+        assert_eq!(parse_date("2000-02-29 12:00", 0.0), Some(951_825_600.0));
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let midnight = NOW - 6.0 * HOUR - 7.0 * MINUTE - 8.0;
+        assert_eq!(parse_date("2023-04-05", 0.0), Some(midnight));
+        assert_eq!(parse_date("2023/04/05", 0.0), Some(midnight));
+        assert_eq!(parse_date("2023-04-05T06:07:08Z", 0.0), Some(NOW));
+        assert_eq!(parse_date("2023-04-05T08:07:08+02:00", 0.0), Some(NOW));
+        assert_eq!(parse_date("2023-04-05 01:07:08 -0500", 0.0), Some(NOW));
+        assert_eq!(parse_date("05-Apr-2023 06:07", 0.0), Some(NOW - 8.0));
+        assert_eq!(parse_date("5 April 2023", 0.0), Some(midnight));
+        assert_eq!(parse_date("Apr 5, 2023 6:07:08 AM", 0.0), Some(NOW));
+        assert_eq!(
+            parse_date("April 4, 2023 6:07 pm", 0.0),
+            Some(NOW - 12.0 * HOUR - 8.0)
+        );
+        assert_eq!(parse_date("2023-04-05 06:07 UTC", 0.0), Some(NOW - 8.0));
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        assert_eq!(parse_date("just now", NOW), Some(NOW));
+        assert_eq!(parse_date("Yesterday", NOW), Some(NOW - DAY));
+        assert_eq!(parse_date("3 hours ago", NOW), Some(NOW - 3.0 * HOUR));
+        assert_eq!(parse_date("an hour ago", NOW), Some(NOW - HOUR));
+        assert_eq!(parse_date("1 min ago", NOW), Some(NOW - MINUTE));
+        assert_eq!(parse_date("2 weeks ago", NOW), Some(NOW - 14.0 * DAY));
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        for input in [
+            "",
+            "-",
+            "unknown",
+            "2023-02-29 00:00",
+            "2023-13-01",
+            "2023-04-05 24:00",
+            "2023-04-05 06:07 tomorrow",
+            "3 parsecs ago",
+            "12345",
+            "Apr 2023",
+        ] {
+            assert_eq!(parse_date(input, NOW), None, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use alloc::string::String;
+
+/// Named entities likely to show up in Madokami descriptions and titles: the
+/// full Latin-1 set plus the common HTML5 punctuation, Greek, arrow and math
+/// entities.
+#[rustfmt::skip]
+const NAMED_ENTITIES: &[(&str, &str)] = &[
+    ("AElig", "Æ"), ("Aacute", "Á"), ("Acirc", "Â"), ("Agrave", "À"), ("Alpha", "Α"),
+    ("Aring", "Å"), ("Atilde", "Ã"), ("Auml", "Ä"), ("Beta", "Β"), ("Ccedil", "Ç"),
+    ("Chi", "Χ"), ("Dagger", "‡"), ("Delta", "Δ"), ("ETH", "Ð"), ("Eacute", "É"),
+    ("Ecirc", "Ê"), ("Egrave", "È"), ("Epsilon", "Ε"), ("Eta", "Η"), ("Euml", "Ë"),
+    ("Gamma", "Γ"), ("Iacute", "Í"), ("Icirc", "Î"), ("Igrave", "Ì"), ("Iota", "Ι"),
+    ("Iuml", "Ï"), ("Kappa", "Κ"), ("Lambda", "Λ"), ("Mu", "Μ"), ("Ntilde", "Ñ"),
+    ("Nu", "Ν"), ("OElig", "Œ"), ("Oacute", "Ó"), ("Ocirc", "Ô"), ("Ograve", "Ò"),
+    ("Omega", "Ω"), ("Omicron", "Ο"), ("Oslash", "Ø"), ("Otilde", "Õ"), ("Ouml", "Ö"),
+    ("Phi", "Φ"), ("Pi", "Π"), ("Prime", "″"), ("Psi", "Ψ"), ("Rho", "Ρ"),
+    ("Scaron", "Š"), ("Sigma", "Σ"), ("THORN", "Þ"), ("Tau", "Τ"), ("Theta", "Θ"),
+    ("Uacute", "Ú"), ("Ucirc", "Û"), ("Ugrave", "Ù"), ("Upsilon", "Υ"), ("Uuml", "Ü"),
+    ("Xi", "Ξ"), ("Yacute", "Ý"), ("Yuml", "Ÿ"), ("Zeta", "Ζ"), ("aacute", "á"),
+    ("acirc", "â"), ("acute", "´"), ("aelig", "æ"), ("agrave", "à"), ("alpha", "α"),
+    ("amp", "&"), ("and", "∧"), ("ang", "∠"), ("apos", "'"), ("aring", "å"),
+    ("asymp", "≈"), ("atilde", "ã"), ("auml", "ä"), ("bdquo", "„"), ("beta", "β"),
+    ("brvbar", "¦"), ("bull", "•"), ("cap", "∩"), ("ccedil", "ç"), ("cedil", "¸"),
+    ("cent", "¢"), ("check", "✓"), ("chi", "χ"), ("circ", "ˆ"), ("clubs", "♣"),
+    ("colon", ":"), ("comma", ","), ("cong", "≅"), ("copy", "©"), ("crarr", "↵"),
+    ("cup", "∪"), ("curren", "¤"), ("dArr", "⇓"), ("dagger", "†"), ("darr", "↓"),
+    ("deg", "°"), ("delta", "δ"), ("diams", "♦"), ("divide", "÷"), ("dollar", "$"),
+    ("eacute", "é"), ("ecirc", "ê"), ("egrave", "è"), ("empty", "∅"), ("emsp", "\u{2003}"),
+    ("ensp", "\u{2002}"), ("epsilon", "ε"), ("equals", "="), ("equiv", "≡"), ("eta", "η"),
+    ("eth", "ð"), ("euml", "ë"), ("euro", "€"), ("excl", "!"), ("exist", "∃"),
+    ("fnof", "ƒ"), ("forall", "∀"), ("frac12", "½"), ("frac14", "¼"), ("frac34", "¾"),
+    ("frasl", "⁄"), ("gamma", "γ"), ("ge", "≥"), ("grave", "`"), ("gt", ">"),
+    ("hArr", "⇔"), ("harr", "↔"), ("hearts", "♥"), ("hellip", "…"), ("hyphen", "‐"),
+    ("iacute", "í"), ("icirc", "î"), ("iexcl", "¡"), ("igrave", "ì"), ("infin", "∞"),
+    ("int", "∫"), ("iota", "ι"), ("iquest", "¿"), ("isin", "∈"), ("iuml", "ï"),
+    ("kappa", "κ"), ("lArr", "⇐"), ("lambda", "λ"), ("lang", "⟨"), ("laquo", "«"),
+    ("larr", "←"), ("lcub", "{"), ("lceil", "⌈"), ("ldquo", "“"), ("le", "≤"),
+    ("lfloor", "⌊"), ("lowast", "∗"), ("lowbar", "_"), ("loz", "◊"), ("lpar", "("),
+    ("lrm", "\u{200E}"), ("lsaquo", "‹"), ("lsqb", "["), ("lsquo", "‘"), ("lt", "<"),
+    ("macr", "¯"), ("mdash", "—"), ("micro", "µ"), ("middot", "·"), ("minus", "−"),
+    ("mu", "μ"), ("nabla", "∇"), ("nbsp", "\u{A0}"), ("ndash", "–"), ("ne", "≠"),
+    ("ni", "∋"), ("not", "¬"), ("notin", "∉"), ("nsub", "⊄"), ("ntilde", "ñ"),
+    ("nu", "ν"), ("num", "#"), ("oacute", "ó"), ("ocirc", "ô"), ("oelig", "œ"),
+    ("ograve", "ò"), ("oline", "‾"), ("omega", "ω"), ("omicron", "ο"), ("oplus", "⊕"),
+    ("or", "∨"), ("ordf", "ª"), ("ordm", "º"), ("oslash", "ø"), ("otilde", "õ"),
+    ("otimes", "⊗"), ("ouml", "ö"), ("para", "¶"), ("part", "∂"), ("percnt", "%"),
+    ("period", "."), ("permil", "‰"), ("perp", "⊥"), ("phi", "φ"), ("pi", "π"),
+    ("piv", "ϖ"), ("plus", "+"), ("plusmn", "±"), ("pound", "£"), ("prime", "′"),
+    ("prod", "∏"), ("prop", "∝"), ("psi", "ψ"), ("quest", "?"), ("quot", "\""),
+    ("rArr", "⇒"), ("radic", "√"), ("rang", "⟩"), ("raquo", "»"), ("rarr", "→"),
+    ("rcub", "}"), ("rceil", "⌉"), ("rdquo", "”"), ("reg", "®"), ("rfloor", "⌋"),
+    ("rho", "ρ"), ("rlm", "\u{200F}"), ("rpar", ")"), ("rsaquo", "›"), ("rsqb", "]"),
+    ("rsquo", "’"), ("sbquo", "‚"), ("scaron", "š"), ("sdot", "⋅"), ("sect", "§"),
+    ("semi", ";"), ("shy", "\u{AD}"), ("sigma", "σ"), ("sigmaf", "ς"), ("sim", "∼"),
+    ("sol", "/"), ("spades", "♠"), ("star", "☆"), ("starf", "★"), ("sub", "⊂"),
+    ("sube", "⊆"), ("sum", "∑"), ("sup", "⊃"), ("sup1", "¹"), ("sup2", "²"),
+    ("sup3", "³"), ("supe", "⊇"), ("szlig", "ß"), ("tau", "τ"), ("there4", "∴"),
+    ("theta", "θ"), ("thetasym", "ϑ"), ("thinsp", "\u{2009}"), ("thorn", "þ"), ("tilde", "˜"),
+    ("times", "×"), ("trade", "™"), ("uArr", "⇑"), ("uacute", "ú"), ("uarr", "↑"),
+    ("ucirc", "û"), ("ugrave", "ù"), ("uml", "¨"), ("upsih", "ϒ"), ("upsilon", "υ"),
+    ("uuml", "ü"), ("verbar", "|"), ("vert", "|"), ("xi", "ξ"), ("yacute", "ý"),
+    ("yen", "¥"), ("yuml", "ÿ"), ("zeta", "ζ"), ("zwj", "\u{200D}"), ("zwnj", "\u{200C}"),
+];
+
+/// How HTML5 reads `&#128;` to `&#159;`: as Windows-1252, since that's what
+/// pages meant when they wrote them.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// The longest name in `NAMED_ENTITIES`, so a stray `&` doesn't scan the rest
+/// of the input looking for a `;`.
+const MAX_ENTITY_LEN: usize = 10;
+
+fn decode_numeric(reference: &str) -> Option<char> {
+    let (digits, radix) = match reference.strip_prefix(['x', 'X']) {
+        Some(hex) => (hex, 16),
+        None => (reference, 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let code = u32::from_str_radix(digits, radix).ok()?;
+    Some(match code {
+        0x80..=0x9F => WINDOWS_1252[(code - 0x80) as usize],
+        0 => '\u{FFFD}',
+        _ => char::from_u32(code).unwrap_or('\u{FFFD}'),
+    })
+}
+
+fn named_entity(name: &str) -> Option<&'static str> {
+    NAMED_ENTITIES
+        .iter()
+        .find(|(entity, _)| *entity == name)
+        .map(|(_, value)| *value)
+}
+
+/// Decodes named (`&hellip;`), decimal (`&#8217;`) and hex (`&#x2014;`)
+/// character references in one pass, so `&amp;lt;` becomes `&lt;`.
+/// Unknown or unterminated references are kept as they are.
+pub fn decode_html_entities(input: &str) -> String {
+    let mut decoded = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let reference = rest
+            .find(';')
+            .filter(|&end| end > 0 && end <= MAX_ENTITY_LEN)
+            .map(|end| &rest[..end]);
+        let Some(reference) = reference else {
+            decoded.push('&');
+            continue;
+        };
+        if let Some(number) = reference.strip_prefix('#') {
+            if let Some(c) = decode_numeric(number) {
+                decoded.push(c);
+                rest = &rest[reference.len() + 1..];
+                continue;
+            }
+        } else if let Some(value) = named_entity(reference) {
+            decoded.push_str(value);
+            rest = &rest[reference.len() + 1..];
+            continue;
+        }
+        decoded.push('&');
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Decodes a series description, dropping the inline script some pages put
+/// before the text.
+pub fn clean_description(input: &str) -> String {
+    let decoded = decode_html_entities(input);
+    if let Some(end_idx) = decoded.find("//-->") {
+        if let Some(_start_idx) = decoded[..end_idx].rfind("<!--") {
+            let after_script = decoded[end_idx + 5..].trim();
+            if !after_script.is_empty() {
+                return String::from(after_script);
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_references() {
+        assert_eq!(decode_html_entities("Don&#8217;t"), "Don’t");
+        assert_eq!(decode_html_entities("A&#x2014;B&#X2014;C"), "A—B—C");
+        assert_eq!(decode_html_entities("&#039;quoted&#39;"), "'quoted'");
+        assert_eq!(decode_html_entities("&#150; &#133;"), "– …");
+        assert_eq!(decode_html_entities("&#128578;"), "🙂");
+        assert_eq!(
+            decode_html_entities("&#0;&#xD800;&#x110000;"),
+            "\u{FFFD}\u{FFFD}\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn test_named_references() {
+        assert_eq!(decode_html_entities("Wait&hellip;"), "Wait…");
+        assert_eq!(decode_html_entities("Pok&eacute;mon"), "Pokémon");
+        assert_eq!(decode_html_entities("a&nbsp;b"), "a\u{A0}b");
+        assert_eq!(
+            decode_html_entities("&lt;b&gt; &quot;x&quot; &apos;"),
+            "<b> \"x\" '"
+        );
+        assert_eq!(
+            decode_html_entities("&ldquo;Hi&rdquo; &ndash; &copy; &Omega;"),
+            "“Hi” – © Ω"
+        );
+    }
+
+    #[test]
+    fn test_single_pass() {
+        assert_eq!(decode_html_entities("&amp;lt;"), "&lt;");
+        assert_eq!(decode_html_entities("&amp;#8217;"), "&#8217;");
+    }
+
+    #[test]
+    fn test_unknown_references_are_kept() {
+        assert_eq!(decode_html_entities("Tom & Jerry"), "Tom & Jerry");
+        assert_eq!(
+            decode_html_entities("&bogus; &; &#; &#xZZ; &#12a; &#x+41;"),
+            "&bogus; &; &#; &#xZZ; &#12a; &#x+41;"
+        );
+        assert_eq!(decode_html_entities("&amp &amp;"), "&amp &");
+        assert_eq!(decode_html_entities("a && b; &hellip;"), "a && b; …");
+        assert_eq!(decode_html_entities("trailing &"), "trailing &");
+    }
+
+    #[test]
+    fn test_entity_table() {
+        for (name, value) in NAMED_ENTITIES {
+            assert!(name.len() <= MAX_ENTITY_LEN, "{} is too long", name);
+            assert!(!value.is_empty());
+            assert_eq!(
+                NAMED_ENTITIES
+                    .iter()
+                    .filter(|(other, _)| other == name)
+                    .count(),
+                1
+            );
+        }
+    }
+}
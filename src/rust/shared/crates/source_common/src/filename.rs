@@ -0,0 +1,845 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::extension_len;
+
+/// What a piece of a filename was recognised as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Part of the series title.
+    Title,
+    /// A volume marker such as `v01`, `Vol. 2` or `(v03)`.
+    Volume,
+    /// A chapter marker such as `c012`, `Ch.5`, `#7` or a bare `012`.
+    Chapter,
+    /// A part of a split chapter, such as `part 2`, `pt.2` or the `p1` in
+    /// `c012p1`.
+    Part,
+    /// A scanlation group, written as `[Group]`.
+    Group,
+    /// Release metadata such as `(2019)`, `(Digital)` or `[Complete]`.
+    Tag,
+    /// A ` - ` separator between the title and the numbering.
+    Separator,
+    /// Anything else, usually a chapter subtitle.
+    Text,
+}
+
+impl TokenKind {
+    fn label(self) -> &'static str {
+        match self {
+            TokenKind::Title => "title",
+            TokenKind::Volume => "volume",
+            TokenKind::Chapter => "chapter",
+            TokenKind::Part => "part",
+            TokenKind::Group => "group",
+            TokenKind::Tag => "tag",
+            TokenKind::Separator => "separator",
+            TokenKind::Text => "text",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The text of the token as it appeared in the filename.
+    pub text: String,
+    /// The number carried by volume and chapter tokens.
+    pub value: Option<f32>,
+    /// The end of a range, e.g. `10` for `c001-010`.
+    pub end: Option<f32>,
+}
+
+impl Token {
+    fn new(kind: TokenKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+            value: None,
+            end: None,
+        }
+    }
+
+    fn numbered(kind: TokenKind, text: &str, (value, end): Number) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+            value: Some(value),
+            end,
+        }
+    }
+}
+
+/// The result of tokenizing a filename or book title.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilenameInfo {
+    pub tokens: Vec<Token>,
+    pub volume: Option<f32>,
+    pub chapter: Option<f32>,
+    pub part: Option<u32>,
+    /// Whether the part is only attached to the chapter number, as in
+    /// `c012p1` or `c012-1`, rather than written out like `part 1`.
+    pub part_attached: bool,
+    pub group: Option<String>,
+    /// How sure the parser is about `volume` and `chapter`, from 0 to 100.
+    pub confidence: u8,
+    input: String,
+    notes: Vec<&'static str>,
+}
+
+impl FilenameInfo {
+    /// The chapter number with any part folded in, so that the parts of a
+    /// split chapter get distinct numbers that sort in order: parts of
+    /// chapter 12 become 12.01 to 12.09, ahead of a 12.1 or 12.5 extra. Parts
+    /// past 9 would run into those extras, so they keep the plain chapter
+    /// number.
+    pub fn ordered_chapter(&self) -> Option<f32> {
+        self.chapter.map(|chapter| match self.part {
+            Some(part @ 1..=MAX_ORDERED_PART) => chapter + part as f32 / 100.0,
+            _ => chapter,
+        })
+    }
+
+    /// The first and last chapter of a file that holds several, like a
+    /// volume pack named `v01 c001-008`.
+    pub fn chapter_range(&self) -> Option<(f32, f32)> {
+        let token = self.tokens.iter().find(|t| t.kind == TokenKind::Chapter)?;
+        match (token.value, token.end) {
+            (Some(first), Some(last)) if last > first => Some((first, last)),
+            _ => None,
+        }
+    }
+
+    /// Keys that identify the chapter without depending on the exact
+    /// filename, from least to most specific, so a rename or re-upload keeps
+    /// its chapter ID. The chapter and part come first, then the volume and
+    /// the group, which are only needed to tell apart files that would
+    /// otherwise share a key. Empty when neither a chapter nor a volume was
+    /// found.
+    pub fn chapter_keys(&self) -> Vec<String> {
+        let mut key = match (self.chapter, self.volume) {
+            (Some(chapter), _) => format!("c{}", chapter),
+            (None, Some(volume)) => format!("v{}", volume),
+            (None, None) => return Vec::new(),
+        };
+        if let Some(part) = self.part {
+            key.push_str(&format!("-p{}", part));
+        }
+        let mut keys = vec![key.clone()];
+        if let (Some(volume), Some(_)) = (self.volume, self.chapter) {
+            key.push_str(&format!("-v{}", volume));
+            keys.push(key.clone());
+        }
+        let group: String = self
+            .group
+            .iter()
+            .flat_map(|group| group.chars())
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        if !group.is_empty() {
+            key.push_str(&format!("-g{}", group));
+            keys.push(key);
+        }
+        keys
+    }
+
+    /// Describes how the filename was tokenized, for diagnosing misparses.
+    pub fn explain(&self) -> String {
+        let mut out = format!("input: {:?}\n", self.input);
+        for token in &self.tokens {
+            out.push_str(&format!("  {:<9} {:?}", token.kind.label(), token.text));
+            match (token.value, token.end) {
+                (Some(value), Some(end)) => out.push_str(&format!(" -> {}..{}", value, end)),
+                (Some(value), None) => out.push_str(&format!(" -> {}", value)),
+                _ => {}
+            }
+            out.push('\n');
+        }
+        for note in &self.notes {
+            out.push_str(&format!("  note: {}\n", note));
+        }
+        out.push_str(&format!(
+            "volume={} chapter={} part={} group={} confidence={}",
+            display_number(self.volume),
+            display_number(self.chapter),
+            display_number(self.part.map(|p| p as f32)),
+            self.group.as_deref().unwrap_or("-"),
+            self.confidence
+        ));
+        out
+    }
+}
+
+fn display_number(number: Option<f32>) -> String {
+    number
+        .map(|n| format!("{}", n))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Series whose names contain numbers, so they are never mistaken for
+/// chapter numbers when the folder name differs from the file's title.
+pub fn known_titles() -> impl Iterator<Item = &'static str> {
+    include_str!("exclusions.txt")
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+}
+
+/// A number and the end of its range, if any.
+type Number = (f32, Option<f32>);
+
+const VOLUME_MARKERS: &[&str] = &["volume", "vol.", "vol", "v"];
+const CHAPTER_MARKERS: &[&str] = &["chapter", "chap.", "chap", "ch.", "ch", "c", "#"];
+const PART_MARKERS: &[&str] = &["part", "pt.", "pt"];
+/// The last part that gets its own chapter number in `ordered_chapter`.
+const MAX_ORDERED_PART: u32 = 9;
+const MARKERS: &[(TokenKind, &[&str])] = &[
+    (TokenKind::Volume, VOLUME_MARKERS),
+    (TokenKind::Chapter, CHAPTER_MARKERS),
+    (TokenKind::Part, PART_MARKERS),
+];
+const TAG_WORDS: &[&str] = &[
+    "digital",
+    "complete",
+    "completed",
+    "colored",
+    "coloured",
+    "color",
+    "colour",
+    "official",
+    "hq",
+    "lq",
+    "f",
+    "f2",
+    "fixed",
+    "omnibus",
+    "oneshot",
+    "one-shot",
+    "webtoon",
+    "raw",
+    "english",
+    "en",
+    "jp",
+    "mag",
+    "magazine",
+    "scan",
+    "scans",
+    "web",
+];
+
+enum Lexeme<'a> {
+    Word(&'a str),
+    Bracket(char, &'a str, &'a str),
+    Separator(&'a str),
+}
+
+/// Splits a filename into words, bracketed groups and ` - ` separators.
+fn lex<'a>(input: &'a str) -> Vec<Lexeme<'a>> {
+    let mut lexemes = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    let mut word_start: Option<usize> = None;
+
+    let flush = |lexemes: &mut Vec<Lexeme<'a>>, start: Option<usize>, end: usize| {
+        if let Some(start) = start {
+            let word = &input[start..end];
+            if word.chars().all(|c| matches!(c, '-' | '–' | '—')) {
+                lexemes.push(Lexeme::Separator(word));
+            } else {
+                lexemes.push(Lexeme::Word(word));
+            }
+        }
+    };
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '[' | '(' | '{' => {
+                flush(&mut lexemes, word_start.take(), i);
+                let close = match c {
+                    '[' => ']',
+                    '(' => ')',
+                    _ => '}',
+                };
+                let inner_start = i + c.len_utf8();
+                match input[inner_start..].find(close) {
+                    Some(offset) => {
+                        let end = inner_start + offset;
+                        lexemes.push(Lexeme::Bracket(
+                            c,
+                            input[inner_start..end].trim(),
+                            &input[i..=end],
+                        ));
+                        while chars.next_if(|&(j, _)| j <= end).is_some() {}
+                    }
+                    None => {
+                        lexemes.push(Lexeme::Bracket(c, input[inner_start..].trim(), &input[i..]));
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() || c == '_' => flush(&mut lexemes, word_start.take(), i),
+            _ => {
+                if word_start.is_none() {
+                    word_start = Some(i);
+                }
+            }
+        }
+    }
+    flush(&mut lexemes, word_start.take(), input.len());
+    lexemes
+}
+
+/// Parses `12`, `12.5` or `001-010` into a number and an optional range end.
+fn parse_number_range(text: &str) -> Option<Number> {
+    let text = text.trim_end_matches([',', ':', ';', '.']);
+    match text.split_once(['-', '~']) {
+        Some((start, end)) => {
+            let start = parse_number(start)?;
+            let end = parse_number(
+                end.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '.'),
+            )?;
+            Some((start, Some(end)))
+        }
+        None => parse_number(text).map(|n| (n, None)),
+    }
+}
+
+fn parse_number(text: &str) -> Option<f32> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, "0"));
+    if whole.is_empty()
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    text.parse::<f32>().ok()
+}
+
+/// Matches a word against a list of markers. Returns the number following
+/// the marker, or `Some(None)` when the marker stands alone and the number
+/// is expected in the next word.
+fn match_marker(word: &str, markers: &[&str]) -> Option<Option<Number>> {
+    let lower = word.to_lowercase();
+    for marker in markers {
+        if let Some(rest) = lower.strip_prefix(marker) {
+            let rest = rest.trim_start();
+            if rest.is_empty() {
+                // A lone `v` or `c` is far more likely to be part of a title.
+                return (marker.len() > 1).then_some(None);
+            }
+            if let Some(number) = parse_number_range(rest) {
+                return Some(Some(number));
+            }
+        }
+    }
+    None
+}
+
+/// Splits a chapter word with an attached part, such as `c012p1` or
+/// `c012pt2`, into a chapter and a part token.
+fn split_part_suffix(word: &str) -> Option<(Token, Token)> {
+    if !word.is_ascii() {
+        return None;
+    }
+    let lower = word.to_lowercase();
+    ["part", "pt", "p"].iter().find_map(|marker| {
+        let pos = lower.rfind(marker)?;
+        let (head, tail) = word.split_at(pos);
+        let part = parse_part(&tail[marker.len()..])?;
+        if !head.ends_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        let chapter = match_marker(head, CHAPTER_MARKERS)??;
+        Some((
+            Token::numbered(TokenKind::Chapter, head, chapter),
+            Token::numbered(TokenKind::Part, tail, (part as f32, None)),
+        ))
+    })
+}
+
+fn parse_part(text: &str) -> Option<u32> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse::<u32>().ok().filter(|&part| part > 0)
+}
+
+/// Finds the longest known series title at the start of `input`, returning
+/// its length in bytes.
+fn title_prefix_len(input: &str, manga_title: &str) -> usize {
+    let lower = input.to_lowercase();
+    core::iter::once(manga_title.trim().to_lowercase())
+        .chain(known_titles().map(str::to_lowercase))
+        .filter(|title| !title.is_empty() && lower.len() == input.len())
+        .filter(|title| {
+            lower.starts_with(title.as_str())
+                && lower[title.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric())
+        })
+        .map(|title| title.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Tokenizes a decoded filename (without extension) into title, volume,
+/// chapter, group and tag tokens, using the series title for context.
+pub fn parse_filename(filename: &str, manga_title: &str) -> FilenameInfo {
+    let input = filename.trim();
+    let mut info = FilenameInfo {
+        input: input.to_string(),
+        ..Default::default()
+    };
+
+    let title_len = title_prefix_len(input, manga_title);
+    if title_len > 0 {
+        info.tokens
+            .push(Token::new(TokenKind::Title, input[..title_len].trim()));
+    }
+
+    let lexemes = lex(&input[title_len..]);
+    // Bare numbers are only chapter candidates until we know whether the
+    // filename carries an explicit chapter marker.
+    let mut bare_numbers = Vec::new();
+    let mut iter = lexemes.iter().peekable();
+    while let Some(lexeme) = iter.next() {
+        let token = match *lexeme {
+            Lexeme::Separator(text) => Token::new(TokenKind::Separator, text),
+            Lexeme::Bracket(open, inner, full) => {
+                let lower = inner.to_lowercase();
+                let marked = MARKERS.iter().find_map(|&(kind, markers)| {
+                    match_marker(inner, markers)
+                        .flatten()
+                        .map(|number| (kind, number))
+                });
+                if let Some((kind, number)) = marked {
+                    Token::numbered(kind, full, number)
+                } else if open == '[' && !is_year(&lower) && !TAG_WORDS.contains(&lower.as_str()) {
+                    Token::new(TokenKind::Group, inner)
+                } else {
+                    Token::new(TokenKind::Tag, full)
+                }
+            }
+            Lexeme::Word(word) => {
+                let matches: Vec<(TokenKind, Option<Number>)> = MARKERS
+                    .iter()
+                    .filter_map(|&(kind, markers)| match_marker(word, markers).map(|m| (kind, m)))
+                    .collect();
+                let marked = matches
+                    .iter()
+                    .find_map(|&(kind, number)| number.map(|number| (kind, number)));
+                if let Some((chapter, part)) = split_part_suffix(word) {
+                    info.tokens.push(chapter);
+                    info.part_attached = true;
+                    part
+                } else if let Some((kind, number)) = marked {
+                    Token::numbered(kind, word, number)
+                } else if let Some(&(kind, None)) = matches.first() {
+                    match iter.peek() {
+                        Some(Lexeme::Word(next)) if parse_number_range(next).is_some() => {
+                            let number = parse_number_range(next).unwrap_or_default();
+                            let text = format!("{} {}", word, next);
+                            iter.next();
+                            Token::numbered(kind, &text, number)
+                        }
+                        _ => Token::new(TokenKind::Text, word),
+                    }
+                } else {
+                    match parse_number_range(word) {
+                        Some(number) => {
+                            bare_numbers.push(info.tokens.len());
+                            Token::numbered(TokenKind::Text, word, number)
+                        }
+                        None => Token::new(TokenKind::Text, word),
+                    }
+                }
+            }
+        };
+        info.tokens.push(token);
+    }
+
+    resolve(&mut info, &bare_numbers, title_len > 0);
+    info
+}
+
+fn is_year(text: &str) -> bool {
+    text.len() == 4
+        && text.bytes().all(|b| b.is_ascii_digit())
+        && (text.starts_with("19") || text.starts_with("20"))
+}
+
+/// Picks the volume, chapter and group out of the classified tokens,
+/// settles which bare number (if any) is the chapter and scores the result.
+fn resolve(info: &mut FilenameInfo, bare_numbers: &[usize], title_matched: bool) {
+    let first_of = |kind: TokenKind| info.tokens.iter().position(|t| t.kind == kind);
+    let first_volume = first_of(TokenKind::Volume);
+    let first_chapter = first_of(TokenKind::Chapter);
+    let first_separator = first_of(TokenKind::Separator);
+
+    let mut score: i32 = 100;
+    let mut chapter_from_bare = None;
+    if first_chapter.is_none() {
+        chapter_from_bare = match first_separator {
+            // `Title - 012 - Subtitle`: the first number after the separator.
+            Some(separator) => bare_numbers.iter().copied().find(|&i| i > separator),
+            // `Title v01 012` or `Title 012`: the last number after the volume,
+            // or the last one that isn't the whole filename.
+            None => bare_numbers
+                .iter()
+                .copied()
+                .rfind(|&i| first_volume.map_or(i > 0 || title_matched, |v| i > v)),
+        };
+    }
+
+    for &i in bare_numbers {
+        if Some(i) == chapter_from_bare {
+            info.tokens[i].kind = TokenKind::Chapter;
+        } else if first_volume.is_some_and(|v| i < v) && first_separator.is_none_or(|s| i < s) {
+            // `Mob Psycho 100 v01`: numbers before the volume are part of the title.
+            info.tokens[i] = Token::new(TokenKind::Title, &info.tokens[i].text);
+        } else {
+            score -= 10;
+            info.notes.push("unused number");
+        }
+    }
+
+    // `JoJo Part 5 v01`: a part ahead of any volume or chapter is part of
+    // the title.
+    let first_number = info
+        .tokens
+        .iter()
+        .position(|t| matches!(t.kind, TokenKind::Volume | TokenKind::Chapter));
+    for (i, token) in info.tokens.iter_mut().enumerate() {
+        if token.kind == TokenKind::Part && first_number.is_none_or(|n| i < n) {
+            *token = Token::new(TokenKind::Title, &token.text);
+        }
+    }
+
+    // Leading words before any numbering belong to the title.
+    for token in info.tokens.iter_mut() {
+        match token.kind {
+            TokenKind::Text => token.kind = TokenKind::Title,
+            TokenKind::Title => {}
+            _ => break,
+        }
+    }
+
+    // `c012-1` and `c012-2` are the parts of chapter 12, not a range.
+    let mut range_part = None;
+    if let Some(token) = info
+        .tokens
+        .iter_mut()
+        .find(|t| t.kind == TokenKind::Chapter)
+    {
+        if let (Some(value), Some(end)) = (token.value, token.end) {
+            if end >= 1.0 && end <= value && end == (end as u32) as f32 {
+                token.end = None;
+                range_part = Some(end as u32);
+                info.notes.push("chapter range read as a part");
+            }
+        }
+    }
+    let written_part = info
+        .tokens
+        .iter()
+        .find(|t| t.kind == TokenKind::Part)
+        .and_then(|t| t.value)
+        .map(|part| part as u32);
+    info.part = written_part.or(range_part);
+    info.part_attached = match written_part {
+        Some(_) => info.part_attached,
+        None => range_part.is_some(),
+    };
+
+    info.volume = info
+        .tokens
+        .iter()
+        .find(|t| t.kind == TokenKind::Volume)
+        .and_then(|t| t.value);
+    info.chapter = info
+        .tokens
+        .iter()
+        .find(|t| t.kind == TokenKind::Chapter)
+        .and_then(|t| t.value);
+    info.group = info
+        .tokens
+        .iter()
+        .rev()
+        .find(|t| t.kind == TokenKind::Group)
+        .map(|t| t.text.clone());
+
+    let chapters: Vec<f32> = info
+        .tokens
+        .iter()
+        .filter(|t| t.kind == TokenKind::Chapter)
+        .filter_map(|t| t.value)
+        .collect();
+    if chapters.windows(2).any(|w| w[0] != w[1]) {
+        score -= 20;
+        info.notes.push("conflicting chapter numbers");
+    }
+    if info
+        .tokens
+        .iter()
+        .filter(|t| t.kind == TokenKind::Volume)
+        .count()
+        > 1
+    {
+        score -= 10;
+        info.notes.push("more than one volume marker");
+    }
+
+    match (info.volume, info.chapter) {
+        (_, Some(_)) if chapter_from_bare.is_some() => {
+            score -= 20;
+            info.notes.push("chapter taken from a bare number");
+        }
+        (_, Some(_)) => {}
+        (Some(_), None) => {
+            score -= 10;
+            info.notes.push("volume without chapter");
+        }
+        (None, None) => {
+            score -= 60;
+            info.notes.push("no volume or chapter found");
+        }
+    }
+    if !title_matched {
+        score -= 10;
+        info.notes.push("series title not found in filename");
+    }
+
+    info.confidence = score.clamp(0, 100) as u8;
+}
+
+/// Strips a known archive, document or image extension from a filename.
+pub fn clean_filename(filename: &str) -> String {
+    let mut cleaned = filename.to_string();
+    if let Some(len) = extension_len(&cleaned) {
+        cleaned.truncate(cleaned.len() - len);
+    }
+    cleaned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(filename: &str, title: &str) -> (Option<f32>, Option<f32>) {
+        let info = parse_filename(filename, title);
+        (info.volume, info.chapter)
+    }
+
+    #[test]
+    fn test_explicit_markers() {
+        assert_eq!(parse("Berserk v01 c001", "Berserk"), (Some(1.0), Some(1.0)));
+        assert_eq!(
+            parse("Berserk c012.5 [Group]", "Berserk"),
+            (None, Some(12.5))
+        );
+        assert_eq!(
+            parse("Berserk Vol. 3 Ch. 20", "Berserk"),
+            (Some(3.0), Some(20.0))
+        );
+        assert_eq!(
+            parse("One Piece - c1000 (v100) [Omega]", "One Piece"),
+            (Some(100.0), Some(1000.0))
+        );
+        assert_eq!(parse("Berserk v01-03", "Berserk"), (Some(1.0), None));
+        assert_eq!(parse("Berserk c001-010", "Berserk"), (None, Some(1.0)));
+    }
+
+    #[test]
+    fn test_bare_numbers() {
+        assert_eq!(parse("Berserk 012", "Berserk"), (None, Some(12.0)));
+        assert_eq!(
+            parse("Berserk - 012 - The Black Swordsman", "Berserk"),
+            (None, Some(12.0))
+        );
+        assert_eq!(parse("Berserk v02 015", "Berserk"), (Some(2.0), Some(15.0)));
+        assert_eq!(parse("Berserk (2019) (Digital)", "Berserk"), (None, None));
+    }
+
+    #[test]
+    fn test_titles_with_numbers() {
+        assert_eq!(
+            parse("Kaiju No. 8 - c045", "Kaiju No. 8"),
+            (None, Some(45.0))
+        );
+        assert_eq!(
+            parse("Mob Psycho 100 v01", "Mob Psycho 100"),
+            (Some(1.0), None)
+        );
+        assert_eq!(parse("Mob Psycho 100 v01", "Mob Psycho"), (Some(1.0), None));
+        assert_eq!(
+            parse("20th Century Boys v03", "Twentieth Century Boys"),
+            (Some(3.0), None)
+        );
+        assert_eq!(parse("7 Seeds", "7 Seeds"), (None, None));
+    }
+
+    #[test]
+    fn test_v_inside_title_is_not_a_volume() {
+        assert_eq!(
+            parse("Rev2ve Heaven c003", "Rev2ve Heaven"),
+            (None, Some(3.0))
+        );
+        assert_eq!(parse("Love2 Hina c003", "Other"), (None, Some(3.0)));
+        assert_eq!(parse("Level 5 c003", "Other"), (None, Some(3.0)));
+    }
+
+    #[test]
+    fn test_groups_and_tags() {
+        let info = parse_filename(
+            "Berserk v01 (2019) (Digital) [Digital] [danke-Empire]",
+            "Berserk",
+        );
+        assert_eq!(info.group.as_deref(), Some("danke-Empire"));
+        let kinds: Vec<TokenKind> = info.tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Title,
+                TokenKind::Volume,
+                TokenKind::Tag,
+                TokenKind::Tag,
+                TokenKind::Tag,
+                TokenKind::Group
+            ]
+        );
+    }
+
+    #[test]
+    fn test_confidence() {
+        assert_eq!(
+            parse_filename("Berserk v01 c001", "Berserk").confidence,
+            100
+        );
+        assert!(parse_filename("Berserk 012", "Berserk").confidence < 100);
+        assert!(parse_filename("Something else", "Berserk").confidence < 50);
+        assert!(
+            parse_filename("Berserk c001 c002", "Berserk").confidence
+                < parse_filename("Berserk c001", "Berserk").confidence
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let explained = parse_filename("Berserk v01 c001 [Group]", "Berserk").explain();
+        assert!(explained.contains("volume    \"v01\" -> 1"));
+        assert!(explained.contains("chapter   \"c001\" -> 1"));
+        assert!(explained.ends_with("volume=1 chapter=1 part=- group=Group confidence=100"));
+    }
+
+    #[test]
+    fn test_clean_filename() {
+        assert_eq!(clean_filename("Berserk v01.mobi"), "Berserk v01");
+        assert_eq!(clean_filename("Berserk c001.CB7"), "Berserk c001");
+        assert_eq!(
+            clean_filename("Berserk c001 [Group].zip"),
+            "Berserk c001 [Group]"
+        );
+        assert_eq!(clean_filename("Berserk v01"), "Berserk v01");
+    }
+
+    #[test]
+    fn test_parts() {
+        let part = |filename: &str| {
+            let info = parse_filename(filename, "Berserk");
+            (info.chapter, info.part, info.ordered_chapter())
+        };
+        assert_eq!(
+            part("Berserk c012 part 1"),
+            (Some(12.0), Some(1), Some(12.01))
+        );
+        assert_eq!(
+            part("Berserk c012 Part 2 [Group]"),
+            (Some(12.0), Some(2), Some(12.02))
+        );
+        assert_eq!(
+            part("Berserk c012 (Part 2)"),
+            (Some(12.0), Some(2), Some(12.02))
+        );
+        assert_eq!(
+            part("Berserk c012 pt.2"),
+            (Some(12.0), Some(2), Some(12.02))
+        );
+        assert_eq!(part("Berserk c012p1"), (Some(12.0), Some(1), Some(12.01)));
+        assert_eq!(part("Berserk c012-1"), (Some(12.0), Some(1), Some(12.01)));
+        assert_eq!(part("Berserk c012-2"), (Some(12.0), Some(2), Some(12.02)));
+        assert_eq!(part("Berserk c001-010"), (Some(1.0), None, Some(1.0)));
+        let attached = |filename: &str| parse_filename(filename, "Berserk").part_attached;
+        assert!(attached("Berserk c012p1"));
+        assert!(attached("Berserk c012-1"));
+        assert!(!attached("Berserk c012 part 1"));
+        assert!(!attached("Berserk c012 (Part 2)"));
+        assert_eq!(
+            part("Berserk - 012 part 2"),
+            (Some(12.0), Some(2), Some(12.02))
+        );
+    }
+
+    #[test]
+    fn test_parts_order() {
+        let ordered = |filename: &str| {
+            parse_filename(filename, "Berserk")
+                .ordered_chapter()
+                .unwrap()
+        };
+        assert!(ordered("Berserk c012") < ordered("Berserk c012 part 1"));
+        assert!(ordered("Berserk c012 part 1") < ordered("Berserk c012 part 2"));
+        assert!(ordered("Berserk c012 part 2") < ordered("Berserk c012.5"));
+        assert!(ordered("Berserk c012.5") < ordered("Berserk c013"));
+        // Part 10 would otherwise be numbered like a 12.1 extra.
+        assert_eq!(ordered("Berserk c012 part 9"), 12.09);
+        assert_eq!(ordered("Berserk c012 part 10"), 12.0);
+    }
+
+    #[test]
+    fn test_chapter_keys() {
+        let keys = |filename: &str| parse_filename(filename, "Berserk").chapter_keys();
+        assert_eq!(keys("Berserk c012"), ["c12"]);
+        assert_eq!(
+            keys("Berserk v03 c012.5 [Danke-Empire]"),
+            ["c12.5", "c12.5-v3", "c12.5-v3-gdankeempire"]
+        );
+        assert_eq!(
+            keys("Berserk c012 part 2 [Group]"),
+            ["c12-p2", "c12-p2-ggroup"]
+        );
+        assert_eq!(keys("Berserk v01"), ["v1"]);
+        assert!(keys("Berserk Artbook").is_empty());
+
+        // Renames that keep the chapter keep the key.
+        assert_eq!(
+            keys("Berserk c012")[0],
+            keys("Berserk - c012 [Digital] [Group]")[0]
+        );
+        assert_eq!(keys("Berserk c012")[0], keys("Bersrek c012")[0]);
+    }
+
+    #[test]
+    fn test_chapter_range() {
+        let range = |filename: &str| parse_filename(filename, "Berserk").chapter_range();
+        assert_eq!(range("Berserk v01 c001-008"), Some((1.0, 8.0)));
+        assert_eq!(range("Berserk v01 (c001-008) [Group]"), Some((1.0, 8.0)));
+        assert_eq!(range("Berserk c012-2"), None);
+        assert_eq!(range("Berserk v01"), None);
+    }
+
+    #[test]
+    fn test_part_in_title() {
+        let info = parse_filename("JoJo Part 5 v01 c001", "Other");
+        assert_eq!(
+            (info.volume, info.chapter, info.part),
+            (Some(1.0), Some(1.0), None)
+        );
+        assert_eq!(info.tokens[1].kind, TokenKind::Title);
+    }
+}
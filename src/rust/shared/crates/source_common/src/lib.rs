@@ -0,0 +1,32 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod archive;
+#[cfg(feature = "aidoku")]
+pub mod cache;
+mod date;
+mod entities;
+mod filename;
+#[cfg(feature = "aidoku")]
+pub mod live;
+mod markup;
+mod model;
+mod network;
+mod opds;
+mod pages;
+mod size;
+mod url;
+mod zip;
+pub use archive::*;
+pub use date::*;
+pub use entities::*;
+pub use filename::*;
+pub use markup::*;
+pub use model::*;
+pub use network::*;
+pub use opds::*;
+pub use pages::*;
+pub use size::*;
+pub use url::*;
+pub use zip::*;
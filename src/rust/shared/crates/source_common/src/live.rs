@@ -0,0 +1,98 @@
+//! Requests over Aidoku's networking, with the retries and credentials every
+//! source sends them with.
+
+use aidoku::std::{
+    current_date,
+    net::{self, HttpMethod, Request},
+    String, StringRef,
+};
+use alloc::format;
+use base64::{engine::general_purpose, Engine};
+
+use crate::Response;
+
+/// The response headers the sources read.
+const RESPONSE_HEADERS: &[&str] = &["Location", "Content-Range"];
+/// How many times a request is sent before a transient failure is reported.
+const MAX_ATTEMPTS: u32 = 3;
+/// The longest `Retry-After`, in seconds, that is still retried. Longer ones
+/// are reported, since the rate limiter only spaces requests out by a second.
+const MAX_RETRY_AFTER: f64 = 2.0;
+/// How many requests a source sends per second. Aidoku holds back the rest,
+/// which also spaces out retries.
+const REQUESTS_PER_SECOND: i32 = 4;
+
+/// Sets up Aidoku's rate limiting, from each source's `initialize`.
+pub fn set_rate_limit() {
+    net::set_rate_limit(REQUESTS_PER_SECOND);
+    net::set_rate_limit_period(1);
+}
+
+/// The `Authorization` header value for HTTP Basic authentication, when both
+/// a username and a password are set.
+pub fn basic_auth(username: &str, password: &str) -> Option<String> {
+    if username.is_empty() || password.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Basic {}",
+        general_purpose::STANDARD.encode(format!("{}:{}", username, password))
+    ))
+}
+
+/// Sends the GET request made by `build`, sending a fresh one while the
+/// server answers 429, 502, 503 or 504, up to `MAX_ATTEMPTS` in all. Retries
+/// go out straight away, spaced only by the rate limiter, and a `Retry-After`
+/// longer than `MAX_RETRY_AFTER` is reported instead.
+pub fn send_with_retry<F: Fn() -> Request>(build: F) -> Request {
+    let mut attempt = 1;
+    loop {
+        let request = build();
+        request.send();
+        if attempt >= MAX_ATTEMPTS || !matches!(request.status_code(), 429 | 502 | 503 | 504) {
+            return request;
+        }
+        let retry_after = request
+            .get_header("Retry-After")
+            .and_then(retry_after_seconds);
+        if retry_after.is_some_and(|seconds| seconds > MAX_RETRY_AFTER) {
+            return request;
+        }
+        attempt += 1;
+    }
+}
+
+/// Reads a `Retry-After` value, which is either a number of seconds or an
+/// HTTP date.
+fn retry_after_seconds(value: StringRef) -> Option<f64> {
+    if let Ok(seconds) = value.read().trim().parse::<f64>() {
+        return Some(seconds);
+    }
+    let date = value.as_date(
+        "EEE, dd MMM yyyy HH:mm:ss zzz",
+        Some("en_US_POSIX"),
+        Some("GMT"),
+    );
+    (date > 0.0).then(|| date - current_date())
+}
+
+/// Sends a GET request to `url` with retries. `prepare` adds the headers every
+/// request of the source carries, and `headers` go on top.
+pub fn get<F: Fn(Request) -> Request>(url: &str, headers: &[(&str, &str)], prepare: F) -> Response {
+    let request = send_with_retry(|| {
+        let mut request = prepare(Request::new(url, HttpMethod::Get));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        request
+    });
+    let headers = RESPONSE_HEADERS
+        .iter()
+        .filter_map(|&name| Some((String::from(name), request.get_header(name)?.read())))
+        .collect();
+    Response {
+        status: request.status_code(),
+        headers,
+        body: request.data(),
+    }
+}
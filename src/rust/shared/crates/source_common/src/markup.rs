@@ -0,0 +1,92 @@
+//! Tag scanning shared by the OPDS feed reader and the HTML reader the
+//! Madokami tests use.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::decode_html_entities;
+
+/// Where the `>` closing a tag is, skipping any inside quoted attributes.
+pub fn tag_end(input: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads a tag's attributes, which may be quoted, unquoted or bare. Names are
+/// lowercased and values have their entities decoded. Only the first of
+/// several attributes with the same name is kept.
+pub fn parse_attributes(input: &str) -> Vec<(String, String)> {
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    let mut rest = input.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len())
+            .max(1);
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = after[1..].find(quote).map_or(after.len(), |end| end + 1);
+                        (&after[1..end], after.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = remaining;
+                decode_html_entities(value)
+            }
+            None => String::new(),
+        };
+        if !attributes.iter().any(|(existing, _)| *existing == name) {
+            attributes.push((name, value));
+        }
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_end() {
+        assert_eq!(tag_end("<a href=\"x>y\">text"), Some(13));
+        assert_eq!(tag_end("<a title='>'/>"), Some(13));
+        assert_eq!(tag_end("<a href=\"x"), None);
+    }
+
+    #[test]
+    fn test_parse_attributes() {
+        let attributes = parse_attributes(
+            " HREF=\"/a?b=1&amp;c=2\" class=mobile title='it\"s' required id=x ID=y /",
+        );
+        let attributes: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("href", "/a?b=1&c=2"),
+                ("class", "mobile"),
+                ("title", "it\"s"),
+                ("required", ""),
+                ("id", "x"),
+            ]
+        );
+    }
+}
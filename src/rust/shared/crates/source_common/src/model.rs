@@ -0,0 +1,52 @@
+//! What the sources' handlers return, kept free of the Aidoku crate so the
+//! handlers can be tested. Each source's Aidoku crate converts these.
+
+use alloc::{string::String, vec::Vec};
+
+/// A series, as Aidoku's `Manga`. The sources always read right to left.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MangaInfo {
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub cover: String,
+    pub description: String,
+    pub categories: Vec<String>,
+    pub url: String,
+    pub completed: bool,
+}
+
+/// The `date_updated` of a chapter whose upload date can't be read, which
+/// Aidoku shows without a date.
+pub const UNKNOWN_DATE: f64 = -1.0;
+
+/// A chapter, as Aidoku's `Chapter`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChapterInfo {
+    pub id: String,
+    pub title: String,
+    pub chapter: f32,
+    pub volume: f32,
+    /// The upload date, or `UNKNOWN_DATE`.
+    pub date_updated: f64,
+    pub scanlator: String,
+    pub url: String,
+}
+
+/// Where a page's image comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageSource {
+    Url(String),
+    /// The image itself, read straight from the chapter's archive.
+    Image(Vec<u8>),
+    /// A message shown in place of the pages, for chapters that can't be
+    /// read in Aidoku.
+    Text(String),
+}
+
+/// A page, as Aidoku's `Page`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageInfo {
+    pub index: i32,
+    pub source: PageSource,
+}
@@ -0,0 +1,156 @@
+//! The network access the sources need, as a trait so their handlers can run
+//! against saved pages in tests instead of the live server.
+
+use alloc::{string::String, vec::Vec};
+
+pub const HTML_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+pub const IMAGE_ACCEPT: &str = "image/*";
+pub const FEED_ACCEPT: &str =
+    "application/atom+xml;profile=opds-catalog,application/atom+xml,application/xml;q=0.9,*/*;q=0.8";
+pub const ARCHIVE_ACCEPT: &str = "application/zip,application/octet-stream,*/*";
+
+/// What came back for a request.
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+    pub status: i32,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// A header's value, matching its name in any case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends GET requests to a source's server.
+pub trait Network {
+    /// Sends a GET request to `url` with `headers` on top of the ones every
+    /// request carries, such as credentials. Redirects are returned as they
+    /// are when the network can see them. Aidoku's own networking follows
+    /// them instead, so callers can't rely on seeing them.
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Response;
+
+    /// The body and final location of a recently fetched page or feed, if
+    /// the network keeps them.
+    fn cached(&self, _url: &str) -> Option<(Vec<u8>, String)> {
+        None
+    }
+
+    /// Keeps the body and final location of a page or feed for `cached`.
+    fn store(&self, _url: &str, _location: &str, _body: &[u8]) {}
+}
+
+/// Answers requests from saved responses, for tests.
+#[cfg(any(test, feature = "fixtures"))]
+#[derive(Default)]
+pub struct FixtureNetwork {
+    responses: Vec<(String, Response)>,
+    requests: core::cell::RefCell<Vec<String>>,
+    settings: core::cell::RefCell<Vec<(String, String)>>,
+}
+
+#[cfg(any(test, feature = "fixtures"))]
+impl FixtureNetwork {
+    /// Answers `url` with `body`.
+    pub fn page(mut self, url: &str, body: &[u8]) -> Self {
+        let response = Response {
+            status: 200,
+            body: Vec::from(body),
+            ..Default::default()
+        };
+        self.responses.push((String::from(url), response));
+        self
+    }
+
+    /// Answers `url` with a status and no body, such as a login wall.
+    pub fn status(mut self, url: &str, status: i32) -> Self {
+        let response = Response {
+            status,
+            ..Default::default()
+        };
+        self.responses.push((String::from(url), response));
+        self
+    }
+
+    /// Answers `url` with a redirect to `location`.
+    pub fn redirect(mut self, url: &str, location: &str) -> Self {
+        let response = Response {
+            status: 301,
+            headers: Vec::from([(String::from("Location"), String::from(location))]),
+            ..Default::default()
+        };
+        self.responses.push((String::from(url), response));
+        self
+    }
+
+    /// A setting's value, standing in for Aidoku's defaults.
+    pub fn setting(&self, key: &str) -> Option<String> {
+        self.settings
+            .borrow()
+            .iter()
+            .find(|(kept, _)| kept == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Sets a setting's value for `setting`.
+    pub fn set_setting(&self, key: &str, value: &str) {
+        let mut settings = self.settings.borrow_mut();
+        settings.retain(|(kept, _)| kept != key);
+        settings.push((String::from(key), String::from(value)));
+    }
+
+    /// The URLs requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.borrow().clone()
+    }
+}
+
+#[cfg(any(test, feature = "fixtures"))]
+impl Network for FixtureNetwork {
+    /// Serves the saved response for `url`, or a 404. Range requests get the
+    /// requested bytes of the body as a 206.
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Response {
+        self.requests.borrow_mut().push(String::from(url));
+        let response = match self.responses.iter().find(|(saved, _)| saved == url) {
+            Some((_, response)) => response.clone(),
+            None => {
+                return Response {
+                    status: 404,
+                    ..Default::default()
+                }
+            }
+        };
+        let range = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Range"))
+            .and_then(|(_, value)| value.strip_prefix("bytes="));
+        match range {
+            Some(range) if response.status == 200 => {
+                let len = response.body.len();
+                let (start, end) = match range.split_once('-') {
+                    Some(("", suffix)) => (len.saturating_sub(suffix.parse().unwrap_or(0)), len),
+                    Some((start, end)) => (
+                        start.parse().unwrap_or(0),
+                        end.parse::<usize>().map_or(len, |end| end + 1).min(len),
+                    ),
+                    None => (0, len),
+                };
+                let start = start.min(end);
+                Response {
+                    status: 206,
+                    headers: Vec::from([(
+                        String::from("Content-Range"),
+                        alloc::format!("bytes {}-{}/{}", start, end.max(1) - 1, len),
+                    )]),
+                    body: Vec::from(&response.body[start..end]),
+                }
+            }
+            _ => response,
+        }
+    }
+}
@@ -0,0 +1,535 @@
+//! Reads OPDS catalogs, the Atom feeds Madokami publishes alongside its
+//! HTML pages and that Komga, Kavita and Calibre-web serve. Only the parts of
+//! a feed the sources use are kept, and the XML is read leniently: unknown
+//! elements are skipped and namespace prefixes are ignored.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{decode_html_entities, parse_attributes, tag_end, url_encode};
+
+/// The `rel` of links to the file itself.
+pub const REL_ACQUISITION: &str = "http://opds-spec.org/acquisition";
+pub const REL_THUMBNAIL: &str = "http://opds-spec.org/image/thumbnail";
+pub const REL_IMAGE: &str = "http://opds-spec.org/image";
+/// The `rel` of a feed's link to its newest entries.
+pub const REL_NEWEST: &str = "http://opds-spec.org/sort/new";
+/// The `rel` of OPDS Page Streaming links, which serve a book one page at a time.
+pub const REL_PAGE_STREAM: &str = "http://vaemendis.net/opds-pse/stream";
+/// The width asked for when a page stream template takes `{maxWidth}`.
+pub const PAGE_STREAM_WIDTH: u32 = 2048;
+
+/// A `<link>` in a feed or entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpdsLink {
+    pub rel: String,
+    pub href: String,
+    /// The media type, from the `type` attribute.
+    pub kind: String,
+    pub title: String,
+    /// The number of pages, from the OPDS-PSE `pse:count` attribute.
+    pub count: Option<u32>,
+    /// The size of the linked file in bytes, from the `length` attribute.
+    pub length: Option<u64>,
+}
+
+impl OpdsLink {
+    /// Whether the link leads to another catalog feed, such as a subfolder.
+    pub fn is_navigation(&self) -> bool {
+        self.kind.starts_with("application/atom+xml")
+            && !self.rel.starts_with(REL_ACQUISITION)
+            && !matches!(
+                self.rel.as_str(),
+                "self" | "alternate" | "search" | "next" | "up"
+            )
+    }
+
+    /// Whether the link downloads the entry's file.
+    pub fn is_acquisition(&self) -> bool {
+        self.rel.starts_with(REL_ACQUISITION)
+    }
+
+    /// Whether the link is to an OpenSearch description rather than straight
+    /// to a search template.
+    pub fn is_search_description(&self) -> bool {
+        self.kind
+            .starts_with("application/opensearchdescription+xml")
+    }
+
+    /// The URL of page `index`, counting from 0, of a page stream link.
+    pub fn page_url(&self, index: u32) -> String {
+        self.href
+            .replace("{pageNumber}", &index.to_string())
+            .replace("{maxWidth}", &PAGE_STREAM_WIDTH.to_string())
+    }
+}
+
+/// An `<entry>`: a folder when it links to another feed, or a file when it
+/// has an acquisition link.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpdsEntry {
+    pub id: String,
+    pub title: String,
+    /// When the entry last changed, as written in the feed.
+    pub updated: String,
+    pub authors: Vec<String>,
+    /// The summary, or the content when there is none, without markup.
+    pub summary: String,
+    pub categories: Vec<String>,
+    pub links: Vec<OpdsLink>,
+}
+
+impl OpdsEntry {
+    /// The link to the entry's own feed, for folders.
+    pub fn navigation(&self) -> Option<&OpdsLink> {
+        self.links.iter().find(|link| link.is_navigation())
+    }
+
+    /// The link to download the entry's file.
+    pub fn acquisition(&self) -> Option<&OpdsLink> {
+        self.links.iter().find(|link| link.is_acquisition())
+    }
+
+    /// The OPDS-PSE link to stream the entry's pages, when the server has one.
+    pub fn page_stream(&self) -> Option<&OpdsLink> {
+        self.links.iter().find(|link| link.rel == REL_PAGE_STREAM)
+    }
+
+    /// The entry's thumbnail, or its full cover when it has no thumbnail.
+    pub fn thumbnail(&self) -> Option<&OpdsLink> {
+        let by_rel = |rel: &str| self.links.iter().find(|link| link.rel == rel);
+        by_rel(REL_THUMBNAIL).or_else(|| by_rel(REL_IMAGE))
+    }
+}
+
+/// A whole catalog feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpdsFeed {
+    pub id: String,
+    pub title: String,
+    pub links: Vec<OpdsLink>,
+    pub entries: Vec<OpdsEntry>,
+}
+
+impl OpdsFeed {
+    /// The feed's first link with the given `rel`, such as `next` or `search`.
+    pub fn link(&self, rel: &str) -> Option<&OpdsLink> {
+        self.links.iter().find(|link| link.rel == rel)
+    }
+}
+
+enum Token<'a> {
+    Open {
+        name: &'a str,
+        attributes: &'a str,
+        empty: bool,
+    },
+    Close(&'a str),
+    Text(String),
+}
+
+/// The name without its namespace prefix, so `pse:count` is `count`.
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Splits XML into tags and text, dropping comments, declarations and
+/// processing instructions. Stops at the first tag that isn't closed.
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            let rest = self.rest;
+            if rest.is_empty() {
+                return None;
+            }
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.rest = &rest[end..];
+                return Some(Token::Text(decode_html_entities(&rest[..end])));
+            }
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment.find("-->")?;
+                self.rest = &comment[end + 3..];
+                continue;
+            }
+            if let Some(data) = rest.strip_prefix("<![CDATA[") {
+                let end = data.find("]]>")?;
+                self.rest = &data[end + 3..];
+                return Some(Token::Text(String::from(&data[..end])));
+            }
+            let end = tag_end(rest)?;
+            let tag = &rest[1..end];
+            self.rest = &rest[end + 1..];
+            if tag.starts_with(['?', '!']) {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Token::Close(local_name(name.trim())));
+            }
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let split = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            return Some(Token::Open {
+                name: local_name(&tag[..split]),
+                attributes: &tag[split..],
+                empty,
+            });
+        }
+    }
+}
+
+/// Reads the attribute called `name` from a tag's attributes, ignoring
+/// namespace prefixes.
+pub(crate) fn attribute(attributes: &str, name: &str) -> Option<String> {
+    parse_attributes(attributes)
+        .into_iter()
+        .find(|(key, _)| local_name(key) == name)
+        .map(|(_, value)| value)
+}
+
+/// The text of an HTML summary: its tags dropped and the entities they were
+/// escaped with decoded.
+fn html_text(input: &str) -> String {
+    let text = decode_html_entities(&strip_tags(input));
+    String::from(text.trim())
+}
+
+/// Drops the tags from HTML, keeping the text between them.
+fn strip_tags(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        match tag_end(&rest[start..]) {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => rest = "",
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn read_link(attributes: &str) -> OpdsLink {
+    let get = |name| attribute(attributes, name).unwrap_or_default();
+    OpdsLink {
+        rel: get("rel"),
+        href: get("href"),
+        kind: get("type"),
+        title: get("title"),
+        count: attribute(attributes, "count").and_then(|count| count.trim().parse().ok()),
+        length: attribute(attributes, "length").and_then(|length| length.trim().parse().ok()),
+    }
+}
+
+/// Parses an OPDS acquisition or navigation feed. Returns `None` when the
+/// document isn't an Atom feed, such as an HTML error page.
+pub fn parse_feed(xml: &str) -> Option<OpdsFeed> {
+    let mut tokens = Tokens { rest: xml };
+    let root = tokens.find(|token| !matches!(token, Token::Text(_)))?;
+    if !matches!(root, Token::Open { name: "feed", .. }) {
+        return None;
+    }
+
+    let mut feed = OpdsFeed::default();
+    let mut entry: Option<OpdsEntry> = None;
+    // The open elements inside the feed, each with where its text starts.
+    let mut open: Vec<(&str, usize)> = Vec::new();
+    let mut text = String::new();
+
+    for token in tokens {
+        match token {
+            Token::Text(chunk) => text.push_str(&chunk),
+            Token::Open {
+                name,
+                attributes,
+                empty,
+            } => {
+                match name {
+                    "entry" if entry.is_none() && open.is_empty() => {
+                        entry = Some(OpdsEntry::default());
+                    }
+                    "link" => {
+                        let link = read_link(attributes);
+                        match entry.as_mut() {
+                            Some(entry) if open.len() == 1 => entry.links.push(link),
+                            None if open.is_empty() => feed.links.push(link),
+                            _ => {}
+                        }
+                    }
+                    "category" => {
+                        let label = attribute(attributes, "label")
+                            .filter(|label| !label.trim().is_empty())
+                            .or_else(|| attribute(attributes, "term"));
+                        if let (Some(entry), Some(label)) = (entry.as_mut(), label) {
+                            entry.categories.push(String::from(label.trim()));
+                        }
+                    }
+                    _ => {}
+                }
+                if !empty {
+                    open.push((name, text.len()));
+                }
+            }
+            Token::Close(name) => {
+                // Skip stray closing tags rather than unwinding to the wrong element.
+                let depth = match open.iter().rposition(|(open, _)| *open == name) {
+                    Some(depth) => depth,
+                    None if name == "feed" => break,
+                    None => continue,
+                };
+                let start = open[depth].1;
+                open.truncate(depth);
+                let value = String::from(text[start..].trim());
+                let parent = open.last().map(|(parent, _)| *parent);
+                match entry.as_mut() {
+                    None => match name {
+                        "title" if open.is_empty() => feed.title = value,
+                        "id" if open.is_empty() => feed.id = value,
+                        _ => {}
+                    },
+                    Some(current) => match (name, parent) {
+                        ("entry", None) => {
+                            feed.entries.extend(entry.take());
+                        }
+                        ("title", Some("entry")) => current.title = value,
+                        ("id", Some("entry")) => current.id = value,
+                        ("updated", Some("entry")) => current.updated = value,
+                        ("published", Some("entry")) if current.updated.is_empty() => {
+                            current.updated = value
+                        }
+                        ("name", Some("author")) if !value.is_empty() => {
+                            current.authors.push(value)
+                        }
+                        ("summary", Some("entry")) => current.summary = html_text(&value),
+                        ("content", Some("entry")) if current.summary.is_empty() => {
+                            current.summary = html_text(&value)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+    }
+    Some(feed)
+}
+
+/// Reads the Atom search template from an OpenSearch description, preferring
+/// one for OPDS catalogs.
+pub fn parse_search_description(xml: &str) -> Option<String> {
+    let mut templates = Tokens { rest: xml }.filter_map(|token| match token {
+        Token::Open {
+            name: "Url",
+            attributes,
+            ..
+        } => Some((
+            attribute(attributes, "type").unwrap_or_default(),
+            attribute(attributes, "template")?,
+        )),
+        _ => None,
+    });
+    let mut fallback = None;
+    for (kind, template) in &mut templates {
+        if kind.contains("profile=opds-catalog") {
+            return Some(template);
+        }
+        if kind.starts_with("application/atom+xml") && fallback.is_none() {
+            fallback = Some(template);
+        }
+    }
+    fallback
+}
+
+/// Fills in an OpenSearch template with the search terms. Optional
+/// parameters such as `{startPage?}` are left empty.
+pub fn fill_search_template(template: &str, query: &str) -> String {
+    let mut url = String::with_capacity(template.len() + query.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        url.push_str(&rest[..start]);
+        let name = &rest[start + 1..end];
+        if name == "searchTerms" || name.ends_with(":searchTerms") {
+            url.push_str(&url_encode(query));
+        }
+        rest = &rest[end + 1..];
+    }
+    url.push_str(rest);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOLDER: &str = include_str!("../fixtures/opds/folder.xml");
+    const SEARCH: &str = include_str!("../fixtures/opds/search.xml");
+    const KOMGA_SERIES: &str = include_str!("../fixtures/opds/komga_series.xml");
+    const KAVITA_ROOT: &str = include_str!("../fixtures/opds/kavita_root.xml");
+    const CALIBRE_BOOKS: &str = include_str!("../fixtures/opds/calibre_books.xml");
+    const CALIBRE_SEARCH: &str = include_str!("../fixtures/opds/calibre_osd.xml");
+
+    #[test]
+    fn test_folder_feed() {
+        let feed = parse_feed(FOLDER).unwrap();
+        assert_eq!(feed.title, "Berserk");
+        assert_eq!(feed.link("next").unwrap().href, "?page=2");
+        assert_eq!(feed.entries.len(), 3);
+
+        let extras = &feed.entries[0];
+        assert_eq!(extras.title, "!Extras");
+        assert_eq!(
+            extras.navigation().unwrap().href,
+            "/opds/Manga/B/BE/BERS/Berserk/%21Extras"
+        );
+        assert!(extras.acquisition().is_none());
+
+        let volume = &feed.entries[1];
+        assert_eq!(volume.title, "Berserk v01 (2003) [Digital].cbz");
+        assert_eq!(volume.updated, "2021-05-04T12:30:00Z");
+        assert_eq!(
+            volume.acquisition().unwrap().href,
+            "/Manga/B/BE/BERS/Berserk/Berserk%20v01%20%282003%29%20%5BDigital%5D.cbz"
+        );
+        assert_eq!(volume.acquisition().unwrap().length, Some(412_876_800));
+        assert!(volume.navigation().is_none());
+        assert_eq!(volume.summary, "The Black Swordsman & his past.");
+
+        assert_eq!(feed.entries[2].title, "Berserk v02 <Digital>.cbz");
+        assert_eq!(feed.entries[2].updated, "2021-05-05T08:00:00Z");
+        assert_eq!(feed.entries[2].acquisition().unwrap().length, None);
+    }
+
+    #[test]
+    fn test_search_feed() {
+        let feed = parse_feed(SEARCH).unwrap();
+        assert_eq!(feed.entries.len(), 2);
+        let series = &feed.entries[0];
+        assert_eq!(series.title, "Shingeki no Kyojin");
+        assert_eq!(series.authors, ["Isayama Hajime"]);
+        assert_eq!(series.categories, ["Action", "Drama"]);
+        assert_eq!(series.summary, "Humanity fights the titans.");
+        assert_eq!(
+            series.thumbnail().unwrap().href,
+            "https://cdn.example.com/snk-thumb.jpg"
+        );
+        assert_eq!(
+            feed.entries[1].thumbnail().unwrap().href,
+            "/covers/kaiju.jpg"
+        );
+    }
+
+    #[test]
+    fn test_not_a_feed() {
+        assert_eq!(
+            parse_feed("<!DOCTYPE html><html><body></body></html>"),
+            None
+        );
+        assert_eq!(parse_feed(""), None);
+        let empty = parse_feed("<?xml version=\"1.0\"?><feed/>").unwrap();
+        assert!(empty.entries.is_empty());
+    }
+
+    #[test]
+    fn test_attribute() {
+        let attributes = r#" rel='next' pse:count="12" href="a?b=1&amp;c=2""#;
+        assert_eq!(attribute(attributes, "rel").as_deref(), Some("next"));
+        assert_eq!(attribute(attributes, "count").as_deref(), Some("12"));
+        assert_eq!(attribute(attributes, "href").as_deref(), Some("a?b=1&c=2"));
+        assert_eq!(attribute(attributes, "type"), None);
+    }
+
+    #[test]
+    fn test_page_stream() {
+        let feed = parse_feed(KOMGA_SERIES).unwrap();
+        assert_eq!(feed.title, "Berserk");
+        assert_eq!(feed.entries.len(), 2);
+        let book = &feed.entries[0];
+        assert_eq!(book.title, "Berserk v01");
+        assert_eq!(book.id, "0B3F6W8V1R2QX");
+        let stream = book.page_stream().unwrap();
+        assert_eq!(stream.count, Some(224));
+        assert_eq!(
+            stream.page_url(0),
+            "/opds/v1.2/books/0B3F6W8V1R2QX/pages/0?zero_based=true"
+        );
+        assert_eq!(
+            book.acquisition().unwrap().href,
+            "/opds/v1.2/books/0B3F6W8V1R2QX/file/Berserk%20v01.cbz"
+        );
+        assert_eq!(
+            book.thumbnail().unwrap().href,
+            "/opds/v1.2/books/0B3F6W8V1R2QX/thumbnail/small"
+        );
+        assert_eq!(
+            feed.link("next").unwrap().href,
+            "/opds/v1.2/series/0B3F5V/?page=1"
+        );
+    }
+
+    #[test]
+    fn test_navigation_feed() {
+        let feed = parse_feed(KAVITA_ROOT).unwrap();
+        let titles: Vec<&str> = feed
+            .entries
+            .iter()
+            .map(|entry| entry.title.as_str())
+            .collect();
+        assert_eq!(titles, ["On Deck", "Libraries", "Manga"]);
+        assert!(feed
+            .entries
+            .iter()
+            .all(|entry| entry.navigation().is_some()));
+        let search = feed.link("search").unwrap();
+        assert!(search.is_search_description());
+        assert_eq!(search.href, "/api/opds/0a1b2c/search");
+        let manga = &feed.entries[2];
+        let stream = manga.page_stream();
+        assert_eq!(stream, None);
+        assert_eq!(
+            manga.navigation().unwrap().href,
+            "/api/opds/0a1b2c/libraries/2"
+        );
+    }
+
+    #[test]
+    fn test_book_feed() {
+        let feed = parse_feed(CALIBRE_BOOKS).unwrap();
+        assert_eq!(feed.entries.len(), 2);
+        let book = &feed.entries[0];
+        assert_eq!(book.title, "Spice and Wolf, Vol. 1");
+        assert_eq!(book.authors, ["Isuna Hasekura", "Jyuu Ayakura"]);
+        assert_eq!(book.acquisition().unwrap().href, "/opds/download/12/epub/");
+        assert_eq!(book.acquisition().unwrap().kind, "application/epub+zip");
+        assert!(book.navigation().is_none());
+        assert!(book.page_stream().is_none());
+        assert_eq!(book.summary, "A merchant meets a wolf goddess.");
+    }
+
+    #[test]
+    fn test_search_description() {
+        let template = parse_search_description(CALIBRE_SEARCH).unwrap();
+        assert_eq!(template, "/opds/search/{searchTerms}");
+        assert_eq!(
+            fill_search_template(&template, "spice & wolf"),
+            "/opds/search/spice%20%26%20wolf"
+        );
+        assert_eq!(
+            fill_search_template("/series?search={searchTerms}&page={startPage?}", "a"),
+            "/series?search=a&page="
+        );
+        assert_eq!(parse_search_description("<OpenSearchDescription/>"), None);
+    }
+}
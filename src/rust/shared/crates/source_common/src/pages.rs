@@ -0,0 +1,247 @@
+use alloc::{string::String, vec::Vec};
+use core::cmp::Ordering;
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    ".jpg", ".jpeg", ".png", ".gif", ".webp", ".avif", ".bmp", ".jxl",
+];
+
+/// What a file in a Madokami folder or OPDS catalog holds, going by its
+/// extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// An archive of page images the reader can open.
+    Pages,
+    /// A document the reader can't show, named by its format, e.g. `PDF`.
+    Document(&'static str),
+    /// Notes and metadata that aren't chapters at all.
+    Text,
+}
+
+/// The extensions of the files found in Madokami folders and OPDS catalogs,
+/// and what each holds.
+/// Page images are kept apart in `IMAGE_EXTENSIONS`.
+const FILE_EXTENSIONS: &[(&str, FileKind)] = &[
+    (".cbz", FileKind::Pages),
+    (".zip", FileKind::Pages),
+    (".cbr", FileKind::Pages),
+    (".rar", FileKind::Pages),
+    (".cb7", FileKind::Pages),
+    (".7z", FileKind::Pages),
+    (".pdf", FileKind::Document("PDF")),
+    (".epub", FileKind::Document("EPUB")),
+    (".mobi", FileKind::Document("MOBI")),
+    (".txt", FileKind::Text),
+    (".nfo", FileKind::Text),
+    (".xml", FileKind::Text),
+    (".json", FileKind::Text),
+    (".md", FileKind::Text),
+    (".htm", FileKind::Text),
+    (".html", FileKind::Text),
+    (".db", FileKind::Text),
+];
+
+/// The entry in `FILE_EXTENSIONS` for a file's extension.
+fn known_file(file: &str) -> Option<&'static (&'static str, FileKind)> {
+    let lower = file.trim().to_lowercase();
+    FILE_EXTENSIONS.iter().find(|(ext, _)| lower.ends_with(ext))
+}
+
+/// The length of a file's extension, including the dot, when it's one found in
+/// Madokami folders and OPDS catalogs or a page image.
+pub fn extension_len(file: &str) -> Option<usize> {
+    let lower = file.to_lowercase();
+    FILE_EXTENSIONS
+        .iter()
+        .map(|(ext, _)| ext)
+        .chain(IMAGE_EXTENSIONS)
+        .find(|ext| lower.ends_with(*ext))
+        .map(|ext| ext.len())
+}
+
+/// Classifies a file by its extension. Anything unrecognised is assumed to be
+/// an archive, so new formats still reach the reader.
+pub fn file_kind(file: &str) -> FileKind {
+    known_file(file).map_or(FileKind::Pages, |&(_, kind)| kind)
+}
+
+/// Whether a path ends in a chapter file, as opposed to a folder.
+pub fn is_chapter_file(path: &str) -> bool {
+    known_file(path).is_some_and(|(_, kind)| *kind != FileKind::Text)
+}
+
+/// Words scanlators put in the filenames of their credit and recruitment pages.
+const CREDIT_WORDS: &[&str] = &["credit", "recruit", "scanlat", "join us", "joinus"];
+
+/// Whether a reader entry is a page image, as opposed to a folder, a
+/// thumbnail cache, a metadata file or macOS resource fork junk.
+pub fn is_page_image(file: &str) -> bool {
+    let lower = file.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or_default();
+    !name.starts_with('.')
+        && !lower.starts_with("__macosx/")
+        && !lower.contains("/__macosx/")
+        && IMAGE_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Whether a page looks like a scanlator credit or recruitment page.
+pub fn is_credit_page(file: &str) -> bool {
+    let lower = file.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or_default();
+    CREDIT_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// Splits a string into alternating runs of digits and non-digits.
+fn chunks(input: &str) -> impl Iterator<Item = &str> {
+    let mut rest = input;
+    core::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let digits = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+/// Orders strings the way people number pages, so `2.jpg` comes before
+/// `10.jpg`. Text is compared case-insensitively and numbers by value, with
+/// fewer leading zeros first when the values tie.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = chunks(a);
+    let mut b_chunks = chunks(b);
+    loop {
+        let (a_chunk, b_chunk) = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_chunk), Some(b_chunk)) => (a_chunk, b_chunk),
+        };
+        let a_digits = a_chunk.starts_with(|c: char| c.is_ascii_digit());
+        let b_digits = b_chunk.starts_with(|c: char| c.is_ascii_digit());
+        let ordering = if a_digits && b_digits {
+            let a_value = a_chunk.trim_start_matches('0');
+            let b_value = b_chunk.trim_start_matches('0');
+            a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value))
+                .then_with(|| a_chunk.len().cmp(&b_chunk.len()))
+        } else {
+            a_chunk
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(b_chunk.chars().flat_map(char::to_lowercase))
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Picks the page images out of a reader's file list and puts them in
+/// reading order, optionally leaving out credit pages.
+pub fn page_files(files: Vec<String>, skip_credits: bool) -> Vec<String> {
+    let mut pages: Vec<String> = files
+        .into_iter()
+        .filter(|file| is_page_image(file))
+        .filter(|file| !skip_credits || !is_credit_page(file))
+        .collect();
+    pages.sort_by(|a, b| natural_cmp(a, b));
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{borrow::ToOwned, vec};
+
+    fn strings(files: &[&str]) -> Vec<String> {
+        files.iter().map(|&file| file.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_file_kind() {
+        assert_eq!(
+            file_kind("Berserk v01 (2003) [Digital].cbz"),
+            FileKind::Pages
+        );
+        assert_eq!(file_kind("Berserk c001.zip"), FileKind::Pages);
+        assert_eq!(file_kind("Berserk c001.CBR"), FileKind::Pages);
+        assert_eq!(
+            file_kind("Berserk v01 [Dark Horse].PDF"),
+            FileKind::Document("PDF")
+        );
+        assert_eq!(
+            file_kind("Spice and Wolf v01.epub"),
+            FileKind::Document("EPUB")
+        );
+        assert_eq!(file_kind("read me.txt"), FileKind::Text);
+        assert_eq!(file_kind("ComicInfo.xml"), FileKind::Text);
+        assert_eq!(file_kind("Vagabond v01"), FileKind::Pages);
+        assert_eq!(file_kind("Berserk c001.cb7"), FileKind::Pages);
+        assert_eq!(file_kind("Berserk v01.mobi"), FileKind::Document("MOBI"));
+        assert!(is_chapter_file("/Manga/B/Berserk/Berserk v01.mobi"));
+        assert!(!is_chapter_file("/Manga/B/Berserk/info.txt"));
+        assert!(!is_chapter_file("/Manga/B/Berserk"));
+        assert_eq!(extension_len("Berserk c001.CB7"), Some(4));
+        assert_eq!(extension_len("cover.jpeg"), Some(5));
+        assert_eq!(extension_len("Vagabond v01"), None);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("2.jpg", "10.jpg"), Ordering::Less);
+        assert_eq!(natural_cmp("page10.png", "Page9.png"), Ordering::Greater);
+        assert_eq!(natural_cmp("001.jpg", "1.jpg"), Ordering::Greater);
+        assert_eq!(natural_cmp("a.jpg", "a.jpg"), Ordering::Equal);
+        assert_eq!(natural_cmp("v01/c002", "v01/c010"), Ordering::Less);
+        assert_eq!(natural_cmp("12a", "12"), Ordering::Greater);
+        assert_eq!(
+            natural_cmp("99999999999999999999999.jpg", "1.jpg"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_page_files() {
+        let files = strings(&[
+            "Thumbs.db",
+            "10.jpg",
+            "credits.txt",
+            "ComicInfo.xml",
+            "2.PNG",
+            "extras/",
+            "extras/1.webp",
+            "__MACOSX/._1.jpg",
+            "._3.jpg",
+            "1.jpeg",
+            "zz_credits.png",
+        ]);
+        assert_eq!(
+            page_files(files.clone(), false),
+            strings(&[
+                "1.jpeg",
+                "2.PNG",
+                "10.jpg",
+                "extras/1.webp",
+                "zz_credits.png"
+            ])
+        );
+        assert_eq!(
+            page_files(files, true),
+            strings(&["1.jpeg", "2.PNG", "10.jpg", "extras/1.webp"])
+        );
+    }
+
+    #[test]
+    fn test_credit_pages() {
+        assert!(is_credit_page("999_Credits.jpg"));
+        assert!(is_credit_page("Recruitment.png"));
+        assert!(is_credit_page("scans/JoinUs.png"));
+        assert!(!is_credit_page("credits/001.jpg"));
+        assert!(!is_credit_page("Incredible Hulk 001.jpg"));
+        assert_eq!(page_files(vec![], true), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,76 @@
+use alloc::{format, string::String};
+
+const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+
+/// Reads a file size as shown in a Madokami listing, such as `20.1 MB`,
+/// `512K` or `1,234 bytes`, into bytes. Sizes are binary, so `1 KB` and
+/// `1 KiB` are both 1024 bytes. `-` and other text without a size give `None`.
+pub fn parse_size(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(input.len());
+    let number: String = input[..split].chars().filter(|&c| c != ',').collect();
+    if number.is_empty() {
+        return None;
+    }
+    let value: f64 = number.parse().ok()?;
+    let unit = input[split..].trim().to_ascii_lowercase();
+    let unit = unit.trim_end_matches("ytes").trim_end_matches('b');
+    let unit = unit.strip_suffix('i').unwrap_or(unit);
+    let exponent = match unit {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => return None,
+    };
+    let bytes = value * (1u64 << (10 * exponent)) as f64;
+    Some(bytes as u64)
+}
+
+/// Formats a size in bytes for display, e.g. `20.1 MB`. Sizes under 100 of a
+/// unit keep one decimal.
+pub fn format_size(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 100.0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("20 MB"), Some(20 * 1024 * 1024));
+        assert_eq!(parse_size("1.5 KB"), Some(1536));
+        assert_eq!(parse_size("512K"), Some(512 * 1024));
+        assert_eq!(parse_size("2 GiB"), Some(2 << 30));
+        assert_eq!(parse_size("1,234 bytes"), Some(1234));
+        assert_eq!(parse_size("1234"), Some(1234));
+        assert_eq!(parse_size(" 18.4 mb "), Some(19_293_798));
+        assert_eq!(parse_size("-"), None);
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("12 parsecs"), None);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1000), "1000 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(20 * 1024 * 1024 + 100_000), "20.1 MB");
+        assert_eq!(format_size(parse_size("4.2 GB").unwrap()), "4.2 GB");
+        assert_eq!(format_size(parse_size("400 MB").unwrap()), "400 MB");
+    }
+}
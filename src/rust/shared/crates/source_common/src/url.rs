@@ -0,0 +1,370 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{clean_filename, decode_html_entities};
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+fn hex_val(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes percent-encoded UTF-8, e.g. `Pok%C3%A9mon` to `Pokémon`.
+/// Malformed escapes are kept as they are, and invalid UTF-8 is replaced
+/// with U+FFFD.
+pub fn url_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(h1), Some(h2)) = (hex_val(bytes[i + 1]), hex_val(bytes[i + 2])) {
+                decoded.push(h1 << 4 | h2);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    match String::from_utf8(decoded) {
+        Ok(decoded) => decoded,
+        Err(error) => String::from_utf8_lossy(error.as_bytes()).into_owned(),
+    }
+}
+
+/// Percent-encodes everything but the RFC 3986 unreserved characters
+/// (`A-Z a-z 0-9 - . _ ~`), the same way Madokami encodes its links.
+pub fn url_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push('%');
+            encoded.push(HEX[(byte >> 4) as usize] as char);
+            encoded.push(HEX[(byte & 15) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Finds a query parameter and decodes it, reading `+` as a space.
+pub fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| url_decode(&value.replace('+', " ")))
+}
+
+/// Percent-encodes each segment of a path, keeping the `/` separators.
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(url_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The one form a Madokami path is stored in: no query, fragment, empty
+/// segments or trailing slash, and each segment percent-encoded the way
+/// Madokami does, however it was encoded before.
+pub fn canonical_path(path: &str) -> String {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let mut canonical = String::with_capacity(path.len());
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        canonical.push('/');
+        canonical.push_str(&url_encode(&url_decode(segment)));
+    }
+    if canonical.is_empty() {
+        canonical.push('/');
+    }
+    canonical
+}
+
+/// The lowercased host of an absolute `http` or `https` URL, with any
+/// credentials and default port removed.
+pub fn url_host(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => ":80",
+        "https" => ":443",
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.strip_suffix(default_port).unwrap_or(host);
+    let host = host.strip_suffix('.').unwrap_or(host);
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Whether `url` points at the same host as `base`. Subdomains and look-alike
+/// hosts such as `manga.madokami.al.example.com` don't count.
+pub fn same_host(url: &str, base: &str) -> bool {
+    match (url_host(url), url_host(base)) {
+        (Some(host), Some(base_host)) => host == base_host,
+        _ => false,
+    }
+}
+
+/// Resolves a link found in a page at `base` into an absolute URL. Handles
+/// absolute, scheme-relative, root-relative and relative links.
+pub fn resolve_url(base: &str, href: &str) -> String {
+    let href = href.trim();
+    let (scheme, rest) = match base.split_once("://") {
+        Some(parts) => parts,
+        None => return String::from(href),
+    };
+    if href.contains("://") {
+        return String::from(href);
+    }
+    if let Some(authority) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, authority);
+    }
+    let path_start = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let origin = &base[..scheme.len() + 3 + path_start];
+    if href.starts_with('/') {
+        return format!("{}{}", origin, href);
+    }
+    let path = rest[path_start..]
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    if href.is_empty() || href.starts_with(['?', '#']) {
+        return format!("{}{}{}", origin, path, href);
+    }
+    let directory = path.rsplit_once('/').map_or("", |(directory, _)| directory);
+    let mut segments: Vec<&str> = directory.split('/').filter(|s| !s.is_empty()).collect();
+    let (href_path, suffix) = match href.find(['?', '#']) {
+        Some(index) => href.split_at(index),
+        None => (href, ""),
+    };
+    let mut parts = href_path.split('/').peekable();
+    while let Some(part) = parts.next() {
+        match part {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ if part.is_empty() && parts.peek().is_some() => {}
+            _ => segments.push(part),
+        }
+    }
+    format!("{}/{}{}", origin, segments.join("/"), suffix)
+}
+
+/// The series title for a folder path: its last segment, skipping `!Extras`
+/// style subfolders and VIZBIG editions.
+pub fn extract_manga_title(path: &str) -> String {
+    path.trim_matches('/')
+        .split('/')
+        .rev()
+        .filter(|part| !part.is_empty())
+        .map(url_decode)
+        .find(|decoded| !decoded.starts_with('!') && !decoded.contains("VIZBIG"))
+        .map(|decoded| clean_filename(&decode_html_entities(&decoded)))
+        .unwrap_or_default()
+}
+
+/// The series folder a path belongs to, leaving out nested `!Extras` style
+/// folders and VIZBIG editions. `None` for the root.
+pub fn get_parent_path(path: &str) -> Option<String> {
+    let parent_parts: Vec<&str> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .take_while(|part| {
+            let decoded = url_decode(part);
+            !decoded.contains("VIZBIG") && !decoded.starts_with('!')
+        })
+        .collect();
+    if parent_parts.is_empty() {
+        None
+    } else {
+        Some(format!("/{}", parent_parts.join("/")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Links as they appear on Madokami.
+    const PATHS: &[&str] = &[
+        "/Manga/S/SH/SHIN/Shingeki%20no%20Kyojin",
+        "/Manga/_Autouploads/AutoUploaded%20from%20Assorted%20Sources/Kimetsu%20no%20Yaiba",
+        "/Manga/Y/YO/YOTS/Yotsuba%26%21",
+        "/Manga/P/PO/POKE/Pok%C3%A9mon%20Adventures",
+        "/Manga/S/SH/SHIN/%E9%80%B2%E6%92%83%E3%81%AE%E5%B7%A8%E4%BA%BA",
+        "/Manga/M/MA/MAGI/Magi%20-%20Sinbad%20no%20Bouken%20%C3%97%20Alibaba",
+        "/Manga/B/BE/BERS/Berserk/Berserk%20v01%20%282003%29%20%5BDigital%5D.cbz",
+        "/Manga/Non-English/Japanese/K/KA/KAIJ/Kaiju%20No.%208",
+    ];
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(url_decode("Pok%C3%A9mon"), "Pokémon");
+        assert_eq!(url_decode("Magi%20%C3%97%20Sinbad"), "Magi × Sinbad");
+        assert_eq!(url_decode("%E9%80%B2%E6%92%83"), "進撃");
+        assert_eq!(url_decode("a%2fb"), "a/b");
+        assert_eq!(url_decode("100%"), "100%");
+        assert_eq!(url_decode("%zz%4"), "%zz%4");
+        assert_eq!(url_decode("%41"), "A");
+        assert_eq!(url_decode("%FF"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            url_encode("Pokémon Adventures"),
+            "Pok%C3%A9mon%20Adventures"
+        );
+        assert_eq!(url_encode("Yotsuba&!"), "Yotsuba%26%21");
+        assert_eq!(url_encode("(2003) [Digital]"), "%282003%29%20%5BDigital%5D");
+        assert_eq!(url_encode("Kaiju No. 8 ~x_y-z"), "Kaiju%20No.%208%20~x_y-z");
+        assert_eq!(encode_path("/Manga/Pokémon/v01"), "/Manga/Pok%C3%A9mon/v01");
+    }
+
+    #[test]
+    fn test_canonical_path() {
+        for path in PATHS {
+            assert_eq!(canonical_path(path), *path);
+        }
+        let canonical = "/Manga/Y/YO/YOTS/Yotsuba%26%21";
+        for variant in [
+            "/Manga/Y/YO/YOTS/Yotsuba&!",
+            "/Manga/Y/YO/YOTS/Yotsuba%26!/",
+            "Manga//Y/YO/YOTS/Yotsuba%26%21",
+            "/Manga/Y/YO/YOTS/Yotsuba%26%21?sort=name#top",
+        ] {
+            assert_eq!(canonical_path(variant), canonical, "{}", variant);
+        }
+        assert_eq!(
+            canonical_path("/Manga/P/PO/POKE/Pok%c3%a9mon Adventures"),
+            "/Manga/P/PO/POKE/Pok%C3%A9mon%20Adventures"
+        );
+        assert_eq!(canonical_path(""), "/");
+        assert_eq!(canonical_path("//"), "/");
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "https://example.com/opds/Manga/B/index.xml?page=2";
+        assert_eq!(
+            resolve_url(base, "/opds/search?q=a"),
+            "https://example.com/opds/search?q=a"
+        );
+        assert_eq!(
+            resolve_url(base, "Berserk/"),
+            "https://example.com/opds/Manga/B/Berserk/"
+        );
+        assert_eq!(
+            resolve_url(base, "../C?page=1"),
+            "https://example.com/opds/Manga/C?page=1"
+        );
+        assert_eq!(
+            resolve_url(base, "?page=3"),
+            "https://example.com/opds/Manga/B/index.xml?page=3"
+        );
+        assert_eq!(
+            resolve_url(base, "//cdn.example.com/a.jpg"),
+            "https://cdn.example.com/a.jpg"
+        );
+        assert_eq!(
+            resolve_url(base, "http://other.example/feed"),
+            "http://other.example/feed"
+        );
+        assert_eq!(
+            resolve_url("https://example.com", "feed.xml"),
+            "https://example.com/feed.xml"
+        );
+    }
+
+    #[test]
+    fn test_same_host() {
+        const BASE: &str = "https://manga.madokami.al";
+        // Pages, including mirrors configured with a port or trailing dot.
+        assert!(same_host(
+            "https://manga.madokami.al/reader/image?path=%2FManga%2FB&file=001.jpg",
+            BASE
+        ));
+        assert!(same_host(
+            "HTTPS://Manga.Madokami.AL:443/reader/image",
+            BASE
+        ));
+        assert!(same_host("https://manga.madokami.al./reader/image", BASE));
+        assert!(same_host(
+            "http://localhost:8080/reader",
+            "http://localhost:8080/"
+        ));
+        assert!(!same_host(
+            "http://localhost:8081/reader",
+            "http://localhost:8080"
+        ));
+
+        // Covers hotlinked from elsewhere.
+        assert!(!same_host(
+            "https://cdn.mangaupdates.com/image/i123456.jpg",
+            BASE
+        ));
+        assert!(!same_host("https://madokami.al/cover.jpg", BASE));
+        assert!(!same_host(
+            "https://manga.madokami.al.example.com/cover.jpg",
+            BASE
+        ));
+        assert!(!same_host(
+            "https://example.com/?next=https://manga.madokami.al",
+            BASE
+        ));
+        assert!(!same_host(
+            "https://manga.madokami.al@example.com/cover.jpg",
+            BASE
+        ));
+        assert!(!same_host("https://example.com#manga.madokami.al", BASE));
+
+        // Relative and non-web URLs never carry credentials.
+        assert!(!same_host("/images/cover.jpg", BASE));
+        assert!(!same_host("ftp://manga.madokami.al/cover.jpg", BASE));
+        assert!(!same_host("", BASE));
+    }
+
+    #[test]
+    fn test_real_paths_round_trip() {
+        for path in PATHS {
+            let decoded: Vec<String> = path.split('/').map(url_decode).collect();
+            assert_eq!(encode_path(&decoded.join("/")), *path);
+            for segment in decoded {
+                assert_eq!(url_decode(&url_encode(&segment)), segment);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_strings_round_trip() {
+        const ALPHABET: &[char] = &[
+            'a', 'Z', '0', ' ', '-', '.', '_', '~', '!', '%', '&', '+', '/', '?', '#', '[', ']',
+            '(', ')', 'é', '×', '’', '進', '撃', '🙂',
+        ];
+        // A small linear congruential generator keeps the cases reproducible.
+        let mut state: u32 = 0x2545_f491;
+        for _ in 0..500 {
+            let mut input = String::new();
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            for _ in 0..(state >> 16) % 24 {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                input.push(ALPHABET[(state >> 16) as usize % ALPHABET.len()]);
+            }
+            let encoded = url_encode(&input);
+            assert!(encoded
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-._~%".contains(&b)));
+            assert_eq!(url_decode(&encoded), input);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+//! Reads ZIP/CBZ archives piece by piece, for use with HTTP range requests:
+//! the end of the archive locates the central directory, which lists every
+//! entry and where its data starts.
+
+use alloc::{string::String, vec::Vec};
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+
+const EOCD_LEN: usize = 22;
+const ZIP64_LOCATOR_LEN: usize = 20;
+const ZIP64_EOCD_LEN: usize = 56;
+const CENTRAL_HEADER_LEN: usize = 46;
+/// The fixed part of a local file header, before the name and extra field.
+pub const LOCAL_HEADER_LEN: usize = 30;
+
+/// How much of the end of an archive to fetch so the end of central directory
+/// record is in it even behind the longest possible comment.
+pub const TAIL_LEN: u64 = (EOCD_LEN + u16::MAX as usize + ZIP64_LOCATOR_LEN) as u64;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const UTF8_NAMES: u16 = 1 << 11;
+const ENCRYPTED: u16 = 1;
+
+/// Filename characters for bytes 0x80 to 0xFF in code page 437, which ZIP
+/// tools use for names not flagged as UTF-8.
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+/// Why an archive couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZipError {
+    /// The data doesn't look like a ZIP archive, or is cut short.
+    Malformed,
+    /// The entry uses a compression method other than stored or deflate.
+    UnsupportedMethod(u16),
+    /// The entry is encrypted.
+    Encrypted,
+    /// The entry's data didn't inflate to its recorded size.
+    Corrupt,
+}
+
+/// Where the central directory is, as recorded at the end of the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CentralDirectory {
+    pub offset: u64,
+    pub size: u64,
+    pub entries: u64,
+}
+
+/// What the end of an archive says about its central directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndOfArchive {
+    Found(CentralDirectory),
+    /// A ZIP64 archive, whose real end record is at this offset.
+    Zip64At(u64),
+}
+
+/// One file in the central directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    pub method: u16,
+    pub flags: u16,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    /// Where the entry's local file header starts.
+    pub header_offset: u64,
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Decodes an entry name, as UTF-8 when flagged or valid, otherwise as CP437.
+fn decode_name(bytes: &[u8], flags: u16) -> String {
+    match core::str::from_utf8(bytes) {
+        Ok(name) => String::from(name),
+        Err(_) if flags & UTF8_NAMES != 0 => String::from_utf8_lossy(bytes).into_owned(),
+        Err(_) => bytes
+            .iter()
+            .map(|&b| match b {
+                0x80.. => CP437[b as usize - 0x80],
+                _ => b as char,
+            })
+            .collect(),
+    }
+}
+
+/// Finds the end of central directory record in the last bytes of an
+/// archive. `tail_offset` is where `tail` starts within the archive.
+pub fn find_end_of_archive(tail: &[u8], tail_offset: u64) -> Result<EndOfArchive, ZipError> {
+    let start = (0..=tail.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&at| u32_at(tail, at) == Some(EOCD_SIGNATURE))
+        .ok_or(ZipError::Malformed)?;
+    let record = &tail[start..];
+    let entries = u16_at(record, 10).ok_or(ZipError::Malformed)?;
+    let size = u32_at(record, 12).ok_or(ZipError::Malformed)?;
+    let offset = u32_at(record, 16).ok_or(ZipError::Malformed)?;
+
+    let locator = start.checked_sub(ZIP64_LOCATOR_LEN);
+    if let Some(locator) = locator.filter(|&at| u32_at(tail, at) == Some(ZIP64_LOCATOR_SIGNATURE)) {
+        let zip64_offset = u64_at(tail, locator + 8).ok_or(ZipError::Malformed)?;
+        return Ok(EndOfArchive::Zip64At(zip64_offset));
+    }
+    if offset as u64 + size as u64 > tail_offset + start as u64 {
+        return Err(ZipError::Malformed);
+    }
+    Ok(EndOfArchive::Found(CentralDirectory {
+        offset: offset as u64,
+        size: size as u64,
+        entries: entries as u64,
+    }))
+}
+
+/// Reads a ZIP64 end of central directory record.
+pub fn parse_zip64_end(record: &[u8]) -> Result<CentralDirectory, ZipError> {
+    if record.len() < ZIP64_EOCD_LEN || u32_at(record, 0) != Some(ZIP64_EOCD_SIGNATURE) {
+        return Err(ZipError::Malformed);
+    }
+    Ok(CentralDirectory {
+        entries: u64_at(record, 32).ok_or(ZipError::Malformed)?,
+        size: u64_at(record, 40).ok_or(ZipError::Malformed)?,
+        offset: u64_at(record, 48).ok_or(ZipError::Malformed)?,
+    })
+}
+
+/// Replaces sizes and offsets saturated at `u32::MAX` with the values from a
+/// ZIP64 extra field.
+fn apply_zip64_extra(entry: &mut ZipEntry, mut extra: &[u8]) {
+    while let (Some(id), Some(len)) = (u16_at(extra, 0), u16_at(extra, 2)) {
+        let Some(mut field) = extra.get(4..4 + len as usize) else {
+            return;
+        };
+        extra = &extra[4 + len as usize..];
+        if id != 0x0001 {
+            continue;
+        }
+        for value in [
+            &mut entry.uncompressed_size,
+            &mut entry.compressed_size,
+            &mut entry.header_offset,
+        ] {
+            if *value == u32::MAX as u64 {
+                let Some(wide) = u64_at(field, 0) else {
+                    return;
+                };
+                *value = wide;
+                field = &field[8..];
+            }
+        }
+        return;
+    }
+}
+
+/// Lists the entries in a central directory.
+pub fn parse_central_directory(data: &[u8]) -> Result<Vec<ZipEntry>, ZipError> {
+    let mut entries = Vec::new();
+    let mut at = 0;
+    while u32_at(data, at) == Some(CENTRAL_HEADER_SIGNATURE) {
+        let header = data
+            .get(at..at + CENTRAL_HEADER_LEN)
+            .ok_or(ZipError::Malformed)?;
+        let flags = u16_at(header, 8).ok_or(ZipError::Malformed)?;
+        let name_len = u16_at(header, 28).ok_or(ZipError::Malformed)? as usize;
+        let extra_len = u16_at(header, 30).ok_or(ZipError::Malformed)? as usize;
+        let comment_len = u16_at(header, 32).ok_or(ZipError::Malformed)? as usize;
+        let name_start = at + CENTRAL_HEADER_LEN;
+        let extra_start = name_start + name_len;
+        let name = data
+            .get(name_start..extra_start)
+            .ok_or(ZipError::Malformed)?;
+        let extra = data
+            .get(extra_start..extra_start + extra_len)
+            .ok_or(ZipError::Malformed)?;
+
+        let mut entry = ZipEntry {
+            name: decode_name(name, flags),
+            method: u16_at(header, 10).ok_or(ZipError::Malformed)?,
+            flags,
+            compressed_size: u32_at(header, 20).ok_or(ZipError::Malformed)? as u64,
+            uncompressed_size: u32_at(header, 24).ok_or(ZipError::Malformed)? as u64,
+            header_offset: u32_at(header, 42).ok_or(ZipError::Malformed)? as u64,
+        };
+        apply_zip64_extra(&mut entry, extra);
+        entries.push(entry);
+        at = extra_start + extra_len + comment_len;
+    }
+    if entries.is_empty() && !data.is_empty() {
+        return Err(ZipError::Malformed);
+    }
+    Ok(entries)
+}
+
+impl ZipEntry {
+    /// Whether this entry is a folder rather than a file.
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    /// How many bytes from `header_offset` to fetch to be sure of getting the
+    /// whole entry. The local header's extra field can differ from the
+    /// central one, so `slack` bytes are added to cover it.
+    pub fn span(&self, slack: u64) -> u64 {
+        LOCAL_HEADER_LEN as u64 + self.name.len() as u64 + self.compressed_size + slack
+    }
+
+    /// Extracts the entry from bytes starting at its local file header.
+    pub fn extract(&self, local: &[u8]) -> Result<Vec<u8>, ZipError> {
+        if self.flags & ENCRYPTED != 0 {
+            return Err(ZipError::Encrypted);
+        }
+        if u32_at(local, 0) != Some(LOCAL_HEADER_SIGNATURE) {
+            return Err(ZipError::Malformed);
+        }
+        let name_len = u16_at(local, 26).ok_or(ZipError::Malformed)? as usize;
+        let extra_len = u16_at(local, 28).ok_or(ZipError::Malformed)? as usize;
+        let start = LOCAL_HEADER_LEN + name_len + extra_len;
+        let data = usize::try_from(self.compressed_size)
+            .ok()
+            .and_then(|size| local.get(start..start.checked_add(size)?))
+            .ok_or(ZipError::Malformed)?;
+        let expected = usize::try_from(self.uncompressed_size).map_err(|_| ZipError::Corrupt)?;
+        match self.method {
+            STORED if data.len() == expected => Ok(Vec::from(data)),
+            STORED => Err(ZipError::Corrupt),
+            DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(data, expected)
+                .ok()
+                .filter(|inflated| inflated.len() == expected)
+                .ok_or(ZipError::Corrupt),
+            method => Err(ZipError::UnsupportedMethod(method)),
+        }
+    }
+}
+
+/// Reads `Content-Range: bytes 100-199/1000` into the first byte offset and,
+/// when known, the total size of the file.
+pub fn parse_content_range(header: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = header.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// A small CBZ: `ComicInfo.xml` and `01.jpg` are deflated,
+    /// `02.png` is stored and `credits/` is a folder.
+    const ARCHIVE: &[u8] = include_bytes!("../fixtures/sample.cbz");
+
+    fn put16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn entries() -> Vec<ZipEntry> {
+        let EndOfArchive::Found(directory) = find_end_of_archive(ARCHIVE, 0).unwrap() else {
+            panic!("not a ZIP64 archive");
+        };
+        let start = directory.offset as usize;
+        parse_central_directory(&ARCHIVE[start..start + directory.size as usize]).unwrap()
+    }
+
+    #[test]
+    fn test_central_directory() {
+        let entries = entries();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["ComicInfo.xml", "01.jpg", "02.png", "credits/"]);
+        assert_eq!(entries[1].method, DEFLATED);
+        assert_eq!(entries[2].method, STORED);
+        assert!(entries[3].is_dir());
+    }
+
+    #[test]
+    fn test_extract() {
+        let entries = entries();
+        let read = |entry: &ZipEntry| {
+            let start = entry.header_offset as usize;
+            let end = (start as u64 + entry.span(64)).min(ARCHIVE.len() as u64) as usize;
+            entry.extract(&ARCHIVE[start..end]).unwrap()
+        };
+        assert_eq!(read(&entries[1]), vec![0x42; 4096]);
+        assert_eq!(read(&entries[2]), b"\x89PNG stored page".to_vec());
+        assert!(String::from_utf8(read(&entries[0]))
+            .unwrap()
+            .contains("<Series>Berserk</Series>"));
+    }
+
+    #[test]
+    fn test_tail_only() {
+        // Only the last bytes are fetched, as with a range request.
+        let tail_offset = ARCHIVE.len() - 40;
+        let end = find_end_of_archive(&ARCHIVE[tail_offset..], tail_offset as u64);
+        assert!(matches!(end, Ok(EndOfArchive::Found(_))));
+        assert_eq!(
+            find_end_of_archive(b"not a zip", 0),
+            Err(ZipError::Malformed)
+        );
+        assert_eq!(find_end_of_archive(&[], 0), Err(ZipError::Malformed));
+    }
+
+    #[test]
+    fn test_damaged_entries() {
+        let entries = entries();
+        let start = entries[1].header_offset as usize;
+        let end = start + entries[1].span(64) as usize;
+        let mut damaged = ARCHIVE[start..end].to_vec();
+        let data_start = LOCAL_HEADER_LEN + entries[1].name.len();
+        damaged[data_start..data_start + 8].fill(0xFF);
+        assert_eq!(entries[1].extract(&damaged), Err(ZipError::Corrupt));
+        assert_eq!(
+            entries[1].extract(&ARCHIVE[start..start + 10]),
+            Err(ZipError::Malformed)
+        );
+
+        let mut bzip2 = entries[1].clone();
+        bzip2.method = 12;
+        assert_eq!(
+            bzip2.extract(&ARCHIVE[start..end]),
+            Err(ZipError::UnsupportedMethod(12))
+        );
+        let mut encrypted = entries[1].clone();
+        encrypted.flags |= ENCRYPTED;
+        assert_eq!(
+            encrypted.extract(&ARCHIVE[start..end]),
+            Err(ZipError::Encrypted)
+        );
+    }
+
+    #[test]
+    fn test_cp437_and_zip64_entries() {
+        let name = b"Ca\x87a.jpg";
+        let mut directory = Vec::new();
+        put32(&mut directory, CENTRAL_HEADER_SIGNATURE);
+        directory.extend_from_slice(&[0; 4]);
+        put16(&mut directory, 0); // flags
+        put16(&mut directory, STORED);
+        directory.extend_from_slice(&[0; 8]);
+        put32(&mut directory, u32::MAX); // compressed size
+        put32(&mut directory, u32::MAX); // uncompressed size
+        put16(&mut directory, name.len() as u16);
+        put16(&mut directory, 20); // extra field length
+        put16(&mut directory, 0); // comment length
+        directory.extend_from_slice(&[0; 8]);
+        put32(&mut directory, 1234); // header offset
+        directory.extend_from_slice(name);
+        put16(&mut directory, 0x0001);
+        put16(&mut directory, 16);
+        directory.extend_from_slice(&5_000_000_000u64.to_le_bytes());
+        directory.extend_from_slice(&4_000_000_000u64.to_le_bytes());
+
+        let entries = parse_central_directory(&directory).unwrap();
+        assert_eq!(entries[0].name, "Caça.jpg");
+        assert_eq!(entries[0].uncompressed_size, 5_000_000_000);
+        assert_eq!(entries[0].compressed_size, 4_000_000_000);
+        assert_eq!(entries[0].header_offset, 1234);
+    }
+
+    #[test]
+    fn test_zip64_end() {
+        let mut tail = Vec::new();
+        put32(&mut tail, ZIP64_EOCD_SIGNATURE);
+        tail.extend_from_slice(&[0; 28]);
+        tail.extend_from_slice(&70_000u64.to_le_bytes());
+        tail.extend_from_slice(&9_000_000u64.to_le_bytes());
+        tail.extend_from_slice(&6_000_000_000u64.to_le_bytes());
+        put32(&mut tail, ZIP64_LOCATOR_SIGNATURE);
+        put32(&mut tail, 0);
+        tail.extend_from_slice(&6_009_000_000u64.to_le_bytes());
+        put32(&mut tail, 1);
+        put32(&mut tail, EOCD_SIGNATURE);
+        tail.extend_from_slice(&[0xFF; 18]);
+
+        assert_eq!(
+            find_end_of_archive(&tail, 6_009_000_000),
+            Ok(EndOfArchive::Zip64At(6_009_000_000))
+        );
+        assert_eq!(
+            parse_zip64_end(&tail),
+            Ok(CentralDirectory {
+                offset: 6_000_000_000,
+                size: 9_000_000,
+                entries: 70_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/1000"),
+            Some((100, Some(1000)))
+        );
+        assert_eq!(parse_content_range("bytes 0-9/*"), Some((0, None)));
+        assert_eq!(parse_content_range("items 0-9/10"), None);
+    }
+}